@@ -0,0 +1,336 @@
+// Bech32 (BIP173) and bech32m (BIP350) encoding, used for native segwit addresses.
+// Version 0 witness programs use bech32, version 1 and up (taproot) use bech32m.
+// Everything works on 5 bit groups ("u5"), stored one per byte.
+
+extern crate hex;
+
+use error::WarpError;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const GENERATOR: [u32; 5] = [0x3b6a_57b2, 0x2650_8e6d, 0x1ea1_19fa, 0x3d42_33dd, 0x2a14_62b3];
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+const MAX_LENGTH: usize = 90;
+const CHECKSUM_LENGTH: usize = 6;
+
+/// The checksum flavour, the two only differ in the constant the checksum is xored with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variant {
+    Bech32,
+    Bech32m
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => BECH32_CONST,
+            Variant::Bech32m => BECH32M_CONST
+        }
+    }
+
+    fn from_constant(constant: u32) -> Option<Variant> {
+        match constant {
+            BECH32_CONST => Some(Variant::Bech32),
+            BECH32M_CONST => Some(Variant::Bech32m),
+            _ => None
+        }
+    }
+
+    /**
+     * The variant BIP350 requires for a witness version.
+     */
+    pub fn for_witness_version(version: u8) -> Variant {
+        if version == 0 { Variant::Bech32 } else { Variant::Bech32m }
+    }
+}
+
+fn invalid(reason: &str) -> WarpError {
+    WarpError::InvalidAddress(reason.to_owned())
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    let mut checksum: u32 = 1;
+    for value in values {
+        let top = checksum >> 25;
+        checksum = (checksum & 0x01ff_ffff) << 5 ^ u32::from(*value);
+        for (index, generator) in GENERATOR.iter().enumerate() {
+            if (top >> index) & 1 == 1 {
+                checksum ^= *generator;
+            }
+        }
+    }
+    checksum
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|byte| byte >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|byte| byte & 0x1f));
+    expanded
+}
+
+fn create_checksum(hrp: &str, data: &[u8], variant: Variant) -> Vec<u8> {
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0u8; CHECKSUM_LENGTH]);
+    let checksum = polymod(&values) ^ variant.constant();
+    (0..CHECKSUM_LENGTH).map(|index| ((checksum >> (5 * (5 - index))) & 0x1f) as u8).collect()
+}
+
+/**
+ * Encodes 5 bit data with a lowercase human readable part.
+ */
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> String {
+    let mut encoded = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_LENGTH);
+    encoded.push_str(hrp);
+    encoded.push('1');
+    for value in data.iter().chain(create_checksum(hrp, data, variant).iter()) {
+        encoded.push(CHARSET[*value as usize] as char);
+    }
+    encoded
+}
+
+/**
+ * Splits a bech32 or bech32m string into its (lowercased) human readable part and 5 bit data,
+ * checksum stripped. The variant is whichever checksum matched.
+ */
+pub fn decode(bech: &str) -> Result<(String, Vec<u8>, Variant), WarpError> {
+    if bech.len() > MAX_LENGTH {
+        return Err(invalid("bech32 string is too long"));
+    }
+    if bech.bytes().any(|byte| !(33..=126).contains(&byte)) {
+        return Err(invalid("bech32 string has characters out of range"));
+    }
+    let has_lower = bech.bytes().any(|byte| byte.is_ascii_lowercase());
+    let has_upper = bech.bytes().any(|byte| byte.is_ascii_uppercase());
+    if has_lower && has_upper {
+        return Err(invalid("bech32 string has mixed case"));
+    }
+    let lowered = bech.to_ascii_lowercase();
+    let separator = lowered.rfind('1').ok_or_else(|| invalid("bech32 string has no separator"))?;
+    if separator == 0 || separator + 1 + CHECKSUM_LENGTH > lowered.len() {
+        return Err(invalid("bech32 human readable part or data is too short"));
+    }
+
+    let (hrp, rest) = lowered.split_at(separator);
+    let mut data = Vec::with_capacity(rest.len() - 1);
+    for character in rest[1..].bytes() {
+        let value = CHARSET.iter().position(|c| *c == character).ok_or_else(|| invalid("bech32 string has an invalid character"))?;
+        data.push(value as u8);
+    }
+
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    let variant = Variant::from_constant(polymod(&values)).ok_or(WarpError::ChecksumMismatch)?;
+    data.truncate(data.len() - CHECKSUM_LENGTH);
+    Ok((hrp.to_owned(), data, variant))
+}
+
+/**
+ * Regroups bits, e.g. bytes (8) into bech32 characters (5) and back.
+ */
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, WarpError> {
+    let mut accumulator: u32 = 0;
+    let mut bits: u32 = 0;
+    let max_value: u32 = (1 << to) - 1;
+    let mut converted = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for value in data {
+        let value = u32::from(*value);
+        if value >> from != 0 {
+            return Err(invalid("value does not fit the source bit width"));
+        }
+        accumulator = (accumulator << from) | value;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            converted.push(((accumulator >> bits) & max_value) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            converted.push(((accumulator << (to - bits)) & max_value) as u8);
+        }
+    } else if bits >= from || ((accumulator << (to - bits)) & max_value) != 0 {
+        return Err(invalid("invalid padding"));
+    }
+    Ok(converted)
+}
+
+/**
+ * Encodes a witness program as a segwit address, e.g. bc1q... for version 0 or bc1p... for version 1.
+ */
+pub fn encode_segwit_address(hrp: &str, version: u8, program: &[u8]) -> Result<String, WarpError> {
+    check_witness_program(version, program)?;
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true)?);
+    Ok(encode(hrp, &data, Variant::for_witness_version(version)))
+}
+
+/**
+ * Returns the witness version and program of a segwit address for the expected hrp.
+ */
+pub fn decode_segwit_address(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), WarpError> {
+    let (decoded_hrp, data, variant) = decode(address)?;
+    if decoded_hrp != hrp {
+        return Err(invalid("unexpected human readable part"));
+    }
+    if data.is_empty() {
+        return Err(invalid("missing witness version"));
+    }
+    let version = data[0];
+    if variant != Variant::for_witness_version(version) {
+        return Err(invalid("wrong checksum variant for the witness version"));
+    }
+    let program = convert_bits(&data[1..], 5, 8, false)?;
+    check_witness_program(version, &program)?;
+    Ok((version, program))
+}
+
+fn check_witness_program(version: u8, program: &[u8]) -> Result<(), WarpError> {
+    if version > 16 {
+        return Err(invalid("witness version above 16"));
+    }
+    if program.len() < 2 || program.len() > 40 {
+        return Err(invalid("witness program must be 2 to 40 bytes"));
+    }
+    if version == 0 && program.len() != 20 && program.len() != 32 {
+        return Err(invalid("version 0 witness program must be 20 or 32 bytes"));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_bip173_valid_checksums() {
+    let vectors = vec![
+        "A12UEL5L",
+        "a12uel5l",
+        "an83characterlonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1tt5tgs",
+        "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+        "11qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqc8247j",
+        "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+        "?1ezyfcl"
+    ];
+    for vector in vectors {
+        println!("Testing checksum of [{}]", vector);
+        let (hrp, data, variant) = decode(vector).unwrap();
+        assert_eq!(Variant::Bech32, variant);
+        assert_eq!(vector.to_ascii_lowercase(), encode(&hrp, &data, variant));
+    }
+}
+
+#[test]
+fn test_bip173_invalid_checksums() {
+    let vectors = vec![
+        // hrp character out of range, overall max length exceeded, no separator,
+        // empty hrp, invalid data character, too short checksum, mixed case
+        "\x201nwldj5",
+        "an84characterslonghumanreadablepartthatcontainsthenumber1andtheexcludedcharactersbio1569pvx",
+        "pzry9x0s0muk",
+        "1pzry9x0s0muk",
+        "x1b4n0q5v",
+        "li1dgmt3",
+        "A1G7SGD8",
+        "10a06t8",
+        "1qzzfhee"
+    ];
+    for vector in vectors {
+        println!("Testing invalid bech32 [{}]", vector);
+        assert!(decode(vector).is_err());
+    }
+}
+
+#[test]
+fn test_bip173_segwit_addresses() {
+    let vectors = vec![
+        // hrp, address, scriptPubKey (version 0 so OP_0 <push length> <program>)
+        ("bc", "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4", "0014751e76e8199196d454941c45d1b3a323f1433bd6"),
+        ("tb", "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7", "00201863143c14c5166804bd19203356da136c985678cd4d27a1b8c6329604903262"),
+        ("tb", "tb1qqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesrxh6hy", "0020000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433")
+    ];
+    for tuple in vectors {
+        println!("Testing segwit address [{}]", tuple.1);
+        let (version, program) = decode_segwit_address(tuple.0, tuple.1).unwrap();
+        let script = hex::decode(tuple.2).unwrap();
+        assert_eq!(0, version);
+        assert_eq!(&script[2..], program.as_slice());
+        assert_eq!(tuple.1.to_ascii_lowercase(), encode_segwit_address(tuple.0, version, &program).unwrap());
+    }
+}
+
+#[test]
+fn test_bip173_invalid_segwit_addresses() {
+    let vectors = vec![
+        // invalid hrp, invalid checksum, invalid witness version, invalid program lengths,
+        // mixed case, zero padding of more than 4 bits, non-zero padding, empty data section
+        "tc1qw508d6qejxtdg4y5r3zarvary0c5xw7kg3g4ty",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+        "BC13W508D6QEJXTDG4Y5R3ZARVARY0C5XW7KN40WF2",
+        "bc1rw5uspcuh",
+        "bc10w508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kw5rljs90",
+        "BC1QR508D6QEJXTDG4Y5R3ZARVARYV98GJ9P",
+        "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sL5k7",
+        "bc1zw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4",
+        "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3pjxtptv",
+        "bc1gmk9yu"
+    ];
+    for vector in vectors {
+        println!("Testing invalid segwit address [{}]", vector);
+        let hrp = if vector.to_ascii_lowercase().starts_with("tb") { "tb" } else { "bc" };
+        assert!(decode_segwit_address(hrp, vector).is_err());
+    }
+}
+
+#[test]
+fn test_bip350_valid_checksums() {
+    let vectors = vec![
+        "A1LQFN3A",
+        "a1lqfn3a",
+        "an83characterlonghumanreadablepartthatcontainsthetheexcludedcharactersbioandnumber11sg7hg6",
+        "abcdef1l7aum6echk45nj3s0wdvt2fg8x9yrzpqzd3ryx",
+        "11llllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllllludsr8",
+        "split1checkupstagehandshakeupstreamerranterredcaperredlc445v",
+        "?1v759aa"
+    ];
+    for vector in vectors {
+        println!("Testing bech32m checksum of [{}]", vector);
+        let (hrp, data, variant) = decode(vector).unwrap();
+        assert_eq!(Variant::Bech32m, variant);
+        assert_eq!(vector.to_ascii_lowercase(), encode(&hrp, &data, variant));
+    }
+}
+
+#[test]
+fn test_bip350_segwit_addresses() {
+    let vectors = vec![
+        // hrp, address, scriptPubKey (OP_n <push length> <program>)
+        ("bc", "bc1pw508d6qejxtdg4y5r3zarvary0c5xw7kw508d6qejxtdg4y5r3zarvary0c5xw7kt5nd6y", "5128751e76e8199196d454941c45d1b3a323f1433bd6751e76e8199196d454941c45d1b3a323f1433bd6"),
+        ("bc", "BC1SW50QGDZ25J", "6002751e"),
+        ("tb", "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c", "5120000000c4a5cad46221b2a187905e5266362b99d5e91c6ce24d165dab93e86433"),
+        ("bc", "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0", "512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+    ];
+    for tuple in vectors {
+        println!("Testing segwit address [{}]", tuple.1);
+        let (version, program) = decode_segwit_address(tuple.0, tuple.1).unwrap();
+        let script = hex::decode(tuple.2).unwrap();
+        assert_eq!(script[0] - 0x50, version);
+        assert_eq!(&script[2..], program.as_slice());
+        assert_eq!(tuple.1.to_ascii_lowercase(), encode_segwit_address(tuple.0, version, &program).unwrap());
+    }
+}
+
+#[test]
+fn test_bip350_wrong_variant() {
+    let vectors = vec![
+        // bech32 checksums on version 1 and 16 programs, bech32m checksums on version 0 programs
+        "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqh2y7hd",
+        "BC1S0XLXVLHEMJA6C4DQV22UAPCTQUPFHLXM9H8Z3K2E72Q4K9HCZ7VQ54WELL",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kemeawh",
+        "tb1q0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vq24jc47"
+    ];
+    for vector in vectors {
+        println!("Testing segwit address with the wrong variant [{}]", vector);
+        let hrp = if vector.starts_with("tb") { "tb" } else { "bc" };
+        assert!(decode(vector).is_ok());
+        assert!(decode_segwit_address(hrp, vector).is_err());
+    }
+}
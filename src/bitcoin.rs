@@ -1,33 +1,352 @@
-
 extern crate bitcoin;
 extern crate hex;
 extern crate secp256k1;
 
 use bitcoin::bitcoin::util::base58::{FromBase58, ToBase58};
-use bitcoin::bitcoin::util::address::Privkey;
-use bitcoin::bitcoin::network::constants::Network::Bitcoin;
 use self::secp256k1::Secp256k1;
 use self::secp256k1::key::SecretKey;
-use sha256::hash256;
+use self::secp256k1::key::PublicKey as Secp256k1PublicKey;
+use bech32;
+use error::WarpError;
+use coins::CoinProfile;
+#[cfg(test)]
+use network::Network;
+use sha256::{hash160, hash256, tagged_hash};
+use std::fmt;
+use std::str::FromStr;
 use std::thread;
 
-pub fn private_key_wif_to_public_address(hex: &str) -> String {
-    let owned_string = hex.to_owned();
-    thread::spawn(move || {
-        let sk: Privkey = FromBase58::from_base58check(&owned_string).unwrap();
+// Witness version of taproot outputs.
+const TAPROOT_VERSION: u8 = 1;
+
+/// The raw 32 byte secret exponent of a key, displayed as lowercase hex.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SecretExponent(Vec<u8>);
+
+impl SecretExponent {
+    /**
+     * Only accepts exponents that are valid secp256k1 secret keys (0 < exponent < n).
+     */
+    pub fn from_bytes(exponent: Vec<u8>) -> Result<SecretExponent, WarpError> {
+        let secp = Secp256k1::new();
+        SecretKey::from_slice(&secp, &exponent).map_err(|_| WarpError::InvalidExponent)?;
+        Ok(SecretExponent(exponent))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn to_wif<C: Into<CoinProfile>>(&self, compressed: bool, coin: C) -> Result<PrivateKeyWif, WarpError> {
+        secret_exponent_to_private_key(self.0.clone(), compressed, coin).map(PrivateKeyWif)
+    }
+
+    pub fn to_public_key(&self, compressed: bool) -> Result<PublicKey, WarpError> {
+        secret_exponent_to_public_key(self.0.clone(), compressed).map(PublicKey)
+    }
+}
+
+impl FromStr for SecretExponent {
+    type Err = WarpError;
+
+    fn from_str(hex_string: &str) -> Result<SecretExponent, WarpError> {
+        SecretExponent::from_bytes(hex::decode(hex_string)?)
+    }
+}
+
+impl fmt::Display for SecretExponent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0))
+    }
+}
+
+/// Whether public keys (and with that WIFs and addresses) use the 33 byte compressed or the
+/// 65 byte uncompressed serialization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyFormat {
+    Uncompressed,
+    Compressed
+}
+
+impl KeyFormat {
+    pub fn is_compressed(self) -> bool {
+        self == KeyFormat::Compressed
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<KeyFormat, String> {
+        match format {
+            "uncompressed" => Ok(KeyFormat::Uncompressed),
+            "compressed" => Ok(KeyFormat::Compressed),
+            _ => Err(format!("unknown key format {}", format))
+        }
+    }
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyFormat::Uncompressed => write!(f, "uncompressed"),
+            KeyFormat::Compressed => write!(f, "compressed")
+        }
+    }
+}
+
+/// A private key in Wallet Import Format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrivateKeyWif(String);
+
+impl PrivateKeyWif {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /**
+     * Compressed WIFs carry an extra 0x01 byte, on bitcoin making them start with K or L instead of 5.
+     */
+    pub fn format(&self) -> KeyFormat {
+        // A PrivateKeyWif only holds WIFs that decoded before, so this can not fail.
+        match decode_wif(&self.0) {
+            Ok((_, _, true)) => KeyFormat::Compressed,
+            _ => KeyFormat::Uncompressed
+        }
+    }
+
+    pub fn to_address(&self) -> Result<Address, WarpError> {
+        private_key_wif_to_public_address(&self.0).map(Address)
+    }
+}
+
+impl FromStr for PrivateKeyWif {
+    type Err = WarpError;
+
+    fn from_str(wif: &str) -> Result<PrivateKeyWif, WarpError> {
+        decode_wif(wif)?;
+        Ok(PrivateKeyWif(wif.to_owned()))
+    }
+}
+
+impl fmt::Display for PrivateKeyWif {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A serialized secp256k1 public key (33 bytes compressed, 65 bytes uncompressed).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey(Vec<u8>);
+
+impl PublicKey {
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn is_compressed(&self) -> bool {
+        self.0.len() == 33
+    }
+
+    pub fn to_p2pkh_address<C: Into<CoinProfile>>(&self, coin: C) -> Address {
+        Address(public_key_to_p2pkh_address(&self.0, coin))
+    }
+
+    pub fn to_p2wpkh_address<C: Into<CoinProfile>>(&self, coin: C) -> Result<Address, WarpError> {
+        public_key_to_p2wpkh_address(&self.0, coin).map(Address)
+    }
+
+    pub fn to_p2sh_p2wpkh_address<C: Into<CoinProfile>>(&self, coin: C) -> Result<Address, WarpError> {
+        public_key_to_p2sh_p2wpkh_address(&self.0, coin).map(Address)
+    }
+
+    pub fn to_p2tr_address<C: Into<CoinProfile>>(&self, coin: C) -> Result<Address, WarpError> {
+        public_key_to_p2tr_address(&self.0, coin).map(Address)
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = WarpError;
+
+    fn from_str(hex_string: &str) -> Result<PublicKey, WarpError> {
+        let bytes = hex::decode(hex_string).map_err(|_| WarpError::InvalidPublicKey)?;
         let secp = Secp256k1::new();
-        let pk = sk.to_address(&secp).unwrap();
-        pk.to_base58check()
-    }).join().unwrap()
+        Secp256k1PublicKey::from_slice(&secp, &bytes).map_err(|_| WarpError::InvalidPublicKey)?;
+        Ok(PublicKey(bytes))
+    }
+}
+
+impl fmt::Display for PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", hex::encode(&self.0))
+    }
+}
+
+/// A base58check encoded P2PKH or P2SH address, a bech32 encoded P2WPKH address
+/// or a bech32m encoded P2TR address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Address(String);
+
+impl Address {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Address {
+    type Err = WarpError;
+
+    /**
+     * Accepts addresses of every known coin and network, bech32 ones are checked against the
+     * hrp they start with.
+     */
+    fn from_str(address: &str) -> Result<Address, WarpError> {
+        let lowered = address.to_ascii_lowercase();
+        let hrp = CoinProfile::all().into_iter()
+            .filter_map(|coin| coin.bech32_hrp)
+            .find(|hrp| lowered.starts_with(&format!("{}1", hrp)));
+        if let Some(hrp) = hrp {
+            bech32::decode_segwit_address(hrp, address)?;
+        } else {
+            let _: Vec<u8> = FromBase58::from_base58check(address)?;
+        }
+        Ok(Address(address.to_owned()))
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/**
+ * Splits a WIF into its coin, secret exponent and whether it is for a compressed key.
+ */
+fn decode_wif(wif: &str) -> Result<(CoinProfile, Vec<u8>, bool), WarpError> {
+    let payload: Vec<u8> = FromBase58::from_base58check(wif)?;
+    let compressed = match payload.len() {
+        33 => false,
+        34 if payload[33] == 0x01 => true,
+        length => return Err(WarpError::InvalidWif(format!("unexpected payload length {}", length)))
+    };
+    let coin = CoinProfile::from_wif_version(payload[0])
+        .ok_or_else(|| WarpError::InvalidWif(format!("unknown version byte {:#04x}", payload[0])))?;
+    Ok((coin, payload[1..33].to_vec(), compressed))
+}
+
+/**
+ * The P2PKH address of a WIF, for the coin or network the WIF is for.
+ */
+pub fn private_key_wif_to_public_address(wif: &str) -> Result<String, WarpError> {
+    let (coin, exponent, compressed) = decode_wif(wif)?;
+    let public_key = secret_exponent_to_public_key(exponent, compressed)?;
+    Ok(public_key_to_p2pkh_address(&public_key, coin))
+}
+
+pub fn secret_exponent_to_private_key<C: Into<CoinProfile>>(exponent: Vec<u8>, compressed: bool, coin: C) -> Result<String, WarpError> {
+    let wif_version = coin.into().wif_version;
+    thread::spawn(move || -> Result<String, WarpError> {
+        let secp: Secp256k1 = Secp256k1::new();
+        SecretKey::from_slice(&secp, &exponent).map_err(|_| WarpError::InvalidExponent)?;
+        let mut payload = vec![wif_version];
+        payload.extend(exponent);
+        if compressed {
+            payload.push(0x01);
+        }
+        Ok(payload.to_base58check())
+    }).join()?
+}
+
+pub fn secret_exponent_to_public_key(exponent: Vec<u8>, compressed: bool) -> Result<Vec<u8>, WarpError> {
+    thread::spawn(move || -> Result<Vec<u8>, WarpError> {
+        let secp: Secp256k1 = Secp256k1::new();
+        let secret_key = SecretKey::from_slice(&secp, &exponent).map_err(|_| WarpError::InvalidExponent)?;
+        let public_key = Secp256k1PublicKey::from_secret_key(&secp, &secret_key).map_err(|_| WarpError::InvalidExponent)?;
+        Ok(public_key.serialize_vec(&secp, compressed).to_vec())
+    }).join()?
+}
+
+/**
+ * Every segwit output type needs the coin to have segwit, P2SH wrapped ones included.
+ */
+fn segwit_hrp(coin: CoinProfile) -> Result<&'static str, WarpError> {
+    coin.bech32_hrp.ok_or_else(|| WarpError::InvalidAddress(format!("{} has no segwit addresses", coin)))
+}
+
+/**
+ * Legacy (1..., or m... and n... on the test networks) address of a public key in either format.
+ */
+pub fn public_key_to_p2pkh_address<C: Into<CoinProfile>>(public_key: &[u8], coin: C) -> String {
+    let mut payload = vec![coin.into().p2pkh_version];
+    payload.extend(hash160(public_key));
+    payload.to_base58check()
+}
+
+/**
+ * Native segwit (bech32 bc1q...) address of a public key. Segwit only allows compressed keys.
+ */
+pub fn public_key_to_p2wpkh_address<C: Into<CoinProfile>>(public_key: &[u8], coin: C) -> Result<String, WarpError> {
+    let hrp = segwit_hrp(coin.into())?;
+    if public_key.len() != 33 {
+        return Err(WarpError::InvalidPublicKey);
+    }
+    bech32::encode_segwit_address(hrp, 0, &hash160(public_key))
+}
+
+/**
+ * Nested segwit (3...) address: the P2WPKH output script wrapped in a P2SH output.
+ */
+pub fn public_key_to_p2sh_p2wpkh_address<C: Into<CoinProfile>>(public_key: &[u8], coin: C) -> Result<String, WarpError> {
+    let coin = coin.into();
+    segwit_hrp(coin)?;
+    let mut payload = vec![coin.p2sh_version];
+    payload.extend(p2sh_p2wpkh_script_hash(public_key)?);
+    Ok(payload.to_base58check())
+}
+
+/**
+ * The hash160 of the P2WPKH redeem script a P2SH-P2WPKH address commits to.
+ */
+pub fn p2sh_p2wpkh_script_hash(public_key: &[u8]) -> Result<Vec<u8>, WarpError> {
+    if public_key.len() != 33 {
+        return Err(WarpError::InvalidPublicKey);
+    }
+    // redeemScript: OP_0 <20 byte push> <hash160 of the public key>
+    let mut redeem_script = vec![0x00, 0x14];
+    redeem_script.extend(hash160(public_key));
+    Ok(hash160(&redeem_script))
 }
 
-pub fn secret_exponent_to_private_key(exponent: Vec<u8>, compressed: bool) -> String {
-    thread::spawn(move || {
+/**
+ * The BIP341 output key of a key path only (BIP86) taproot output, as a 32 byte x-only key.
+ *      Q = P + int(tagged_hash("TapTweak", x(P))) * G, where P is the key with even y.
+ */
+pub fn taproot_output_key(public_key: &[u8]) -> Result<Vec<u8>, WarpError> {
+    if public_key.len() != 33 {
+        return Err(WarpError::InvalidPublicKey);
+    }
+    let x_only = public_key[1..].to_vec();
+    thread::spawn(move || -> Result<Vec<u8>, WarpError> {
         let secp: Secp256k1 = Secp256k1::new();
-        let secret_key = SecretKey::from_slice(&secp, &exponent).unwrap();
-        let private_key = bitcoin::util::address::Privkey::from_key(Bitcoin, secret_key, compressed);
-        private_key.to_base58check()
-    }).join().unwrap()
+        // Lifting x with an 0x02 prefix picks the even y point, whatever the parity of the original key.
+        let mut even_key = vec![0x02];
+        even_key.extend_from_slice(&x_only);
+        let mut output_key = Secp256k1PublicKey::from_slice(&secp, &even_key).map_err(|_| WarpError::InvalidPublicKey)?;
+        // An empty script tree commits to the internal key only.
+        let tweak = SecretKey::from_slice(&secp, &tagged_hash("TapTweak", &x_only)).map_err(|_| WarpError::InvalidPublicKey)?;
+        output_key.add_exp_assign(&secp, &tweak).map_err(|_| WarpError::InvalidPublicKey)?;
+        Ok(output_key.serialize_vec(&secp, true)[1..].to_vec())
+    }).join()?
+}
+
+/**
+ * Key path only taproot (bech32m bc1p...) address of a compressed public key, as in BIP86.
+ */
+pub fn public_key_to_p2tr_address<C: Into<CoinProfile>>(public_key: &[u8], coin: C) -> Result<String, WarpError> {
+    let hrp = segwit_hrp(coin.into())?;
+    let output_key = taproot_output_key(public_key)?;
+    bech32::encode_segwit_address(hrp, TAPROOT_VERSION, &output_key)
 }
 
 #[test]
@@ -56,7 +375,7 @@ fn test_private_key_wif_to_address()
     ];
     for tuple in &addresses {
         println!("Testing exponent: [{}]", tuple.1);
-        assert_eq!(tuple.0, private_key_wif_to_public_address(tuple.1));
+        assert_eq!(tuple.0, private_key_wif_to_public_address(tuple.1).unwrap());
     }
 }
 
@@ -71,7 +390,7 @@ fn test_exponent_to_private_key() {
 
     for tuple in test_vectors {
         println!("testing {}", &tuple.0);
-        assert_eq!(tuple.0, secret_exponent_to_private_key(hex::decode(tuple.1).unwrap(), false));
+        assert_eq!(tuple.0, secret_exponent_to_private_key(hex::decode(tuple.1).unwrap(), false, Network::Bitcoin).unwrap());
     }
 }
 
@@ -119,8 +438,285 @@ fn test_private_key_from_hex_string() {
         println!("Testing bytes to hex [{}]", tuple.1);
         assert_eq!(tuple.1, hex::encode(&tuple.2));
         println!("Testing private key wif [{}]", tuple.3);
-        assert_eq!(tuple.3, secret_exponent_to_private_key(tuple.2.to_vec(), false));
+        assert_eq!(tuple.3, secret_exponent_to_private_key(tuple.2.to_vec(), false, Network::Bitcoin).unwrap());
         println!("Testing private wif {} to address {}.", tuple.3, tuple.4);
-        assert_eq!(tuple.4, private_key_wif_to_public_address(tuple.3));
+        assert_eq!(tuple.4, private_key_wif_to_public_address(tuple.3).unwrap());
+    }
+}
+
+#[test]
+fn test_key_types_from_str_and_display() {
+    let exponent: SecretExponent = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".parse().unwrap();
+    assert_eq!("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9", exponent.to_string());
+
+    let wif = exponent.to_wif(false, Network::Bitcoin).unwrap();
+    assert_eq!("5KDtreMojMGybkQngM9MVY7GhAygvx7iZjL7dbcjQjrcMeULjWZ", wif.to_string());
+    assert_eq!(wif, "5KDtreMojMGybkQngM9MVY7GhAygvx7iZjL7dbcjQjrcMeULjWZ".parse().unwrap());
+    assert_eq!("1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMb", wif.to_address().unwrap().to_string());
+
+    let public_key = exponent.to_public_key(false).unwrap();
+    assert_eq!(65, public_key.as_bytes().len());
+    assert!(!public_key.is_compressed());
+    assert_eq!(public_key, public_key.to_string().parse().unwrap());
+    assert!(exponent.to_public_key(true).unwrap().is_compressed());
+}
+
+#[test]
+fn test_invalid_keys_are_errors() {
+    let vectors = vec![
+        // Invalid secret exponent hex strings: zero, the curve order n, too short, not hex
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        "b94d27b9934d3e08a52e52d7da7dabfa",
+        "not a hex string"
+    ];
+    for vector in vectors {
+        println!("Testing invalid exponent [{}]", vector);
+        assert_eq!(Err(WarpError::InvalidExponent), vector.parse::<SecretExponent>());
+    }
+    assert_eq!(Err(WarpError::InvalidExponent), secret_exponent_to_private_key(vec![0u8; 32], false, Network::Bitcoin));
+    assert_eq!(Err(WarpError::InvalidExponent), secret_exponent_to_public_key(vec![0u8; 32], true));
+
+    assert_eq!(
+        Err(WarpError::ChecksumMismatch),
+        "5KDtreMojMGybkQngM9MVY7GhAygvx7iZjL7dbcjQjrcMeULjWz".parse::<PrivateKeyWif>()
+    );
+    assert_eq!(
+        Err(WarpError::ChecksumMismatch),
+        private_key_wif_to_public_address("5KDtreMojMGybkQngM9MVY7GhAygvx7iZjL7dbcjQjrcMeULjWz")
+    );
+    assert!("1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMc".parse::<Address>().is_err());
+    assert_eq!(Err(WarpError::InvalidPublicKey), "not a hex string".parse::<PublicKey>());
+    assert_eq!(Err(WarpError::InvalidPublicKey), "0279be667ef9".parse::<PublicKey>());
+    match "0OIl".parse::<PrivateKeyWif>() {
+        Err(WarpError::InvalidWif(_)) => (),
+        other => panic!("expected an invalid wif error, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_compressed_and_uncompressed_keys() {
+    let vectors = vec![
+        // Secret exponent, compressed wif, compressed address, uncompressed wif, uncompressed address
+        (
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+            "L3Rut7tqFvVWpNbzcfusSxJgGK3DF8GeTTqNGXniBn9y46DqvDSb", "12q7HJP6LFwMHFWCogVzjq7BsHt8tqWfur",
+            "5KDtreMojMGybkQngM9MVY7GhAygvx7iZjL7dbcjQjrcMeULjWZ", "1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMb"
+        )
+    ];
+    for tuple in vectors {
+        println!("Testing exponent [{}]", tuple.0);
+        let exponent: SecretExponent = tuple.0.parse().unwrap();
+
+        let compressed_wif = exponent.to_wif(KeyFormat::Compressed.is_compressed(), Network::Bitcoin).unwrap();
+        assert_eq!(tuple.1, compressed_wif.to_string());
+        assert_eq!(KeyFormat::Compressed, compressed_wif.format());
+        assert_eq!(tuple.2, compressed_wif.to_address().unwrap().to_string());
+
+        let uncompressed_wif = exponent.to_wif(KeyFormat::Uncompressed.is_compressed(), Network::Bitcoin).unwrap();
+        assert_eq!(tuple.3, uncompressed_wif.to_string());
+        assert_eq!(KeyFormat::Uncompressed, uncompressed_wif.format());
+        assert_eq!(tuple.4, uncompressed_wif.to_address().unwrap().to_string());
+    }
+    assert_eq!(Ok(KeyFormat::Compressed), "compressed".parse());
+    assert_eq!("uncompressed", KeyFormat::Uncompressed.to_string());
+    assert!("squashed".parse::<KeyFormat>().is_err());
+}
+
+#[test]
+fn test_p2wpkh_addresses() {
+    let vectors = vec![
+        // Expected P2WPKH address, compressed public key
+        ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+    ];
+    for tuple in vectors {
+        println!("Testing public key [{}]", tuple.1);
+        let public_key: PublicKey = tuple.1.parse().unwrap();
+        assert_eq!(tuple.0, public_key.to_p2wpkh_address(Network::Bitcoin).unwrap().to_string());
+        assert_eq!(tuple.0, tuple.0.parse::<Address>().unwrap().to_string());
+    }
+
+    let exponent: SecretExponent = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".parse().unwrap();
+    let compressed = exponent.to_public_key(true).unwrap();
+    assert_eq!("bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz", compressed.to_p2wpkh_address(Network::Bitcoin).unwrap().to_string());
+
+    let uncompressed = exponent.to_public_key(false).unwrap();
+    assert_eq!(Err(WarpError::InvalidPublicKey), uncompressed.to_p2wpkh_address(Network::Bitcoin));
+    assert_eq!(Err(WarpError::ChecksumMismatch), "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5".parse::<Address>());
+}
+
+#[test]
+fn test_p2sh_p2wpkh_addresses() {
+    let vectors = vec![
+        // Expected P2SH-P2WPKH address, compressed public key
+        ("3JvL6Ymt8MVWiCNHC7oWU6nLeHNJKLZGLN", "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+    ];
+    for tuple in vectors {
+        println!("Testing public key [{}]", tuple.1);
+        let public_key: PublicKey = tuple.1.parse().unwrap();
+        assert_eq!(tuple.0, public_key.to_p2sh_p2wpkh_address(Network::Bitcoin).unwrap().to_string());
+        assert_eq!(tuple.0, tuple.0.parse::<Address>().unwrap().to_string());
+    }
+
+    let exponent: SecretExponent = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".parse().unwrap();
+    assert_eq!(
+        "3KKCYKKJjFtxQa4nPSnk2g8EXPo62r3Pzr",
+        exponent.to_public_key(true).unwrap().to_p2sh_p2wpkh_address(Network::Bitcoin).unwrap().to_string()
+    );
+    assert_eq!(Err(WarpError::InvalidPublicKey), exponent.to_public_key(false).unwrap().to_p2sh_p2wpkh_address(Network::Bitcoin));
+}
+
+#[test]
+fn test_p2tr_addresses() {
+    let vectors = vec![
+        // BIP86 first receiving address (odd y internal key): address, output key, internal key
+        ("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+         "a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c",
+         "03cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115"),
+        // Secret exponent 1 (even y internal key)
+        ("bc1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5sspknck9",
+         "da4710964f7852695de2da025290e24af6d8c281de5a0b902b7135fd9fd74d21",
+         "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+    ];
+    for tuple in vectors {
+        println!("Testing public key [{}]", tuple.2);
+        let public_key: PublicKey = tuple.2.parse().unwrap();
+        assert_eq!(tuple.1, hex::encode(taproot_output_key(public_key.as_bytes()).unwrap()));
+        assert_eq!(tuple.0, public_key.to_p2tr_address(Network::Bitcoin).unwrap().to_string());
+        assert_eq!(tuple.0, tuple.0.parse::<Address>().unwrap().to_string());
+    }
+
+    let wif: PrivateKeyWif = "KyRv5iFPHG7iB5E4CqvMzH3WFJVhbfYK4VY7XAedd9Ys69mEsPLQ".parse().unwrap();
+    let exponent: SecretExponent = "41f41d69260df4cf277826a9b65a3717e4eeddbeedf637f212ca096576479361".parse().unwrap();
+    assert_eq!(wif, exponent.to_wif(true, Network::Bitcoin).unwrap());
+    assert_eq!(
+        "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr",
+        exponent.to_public_key(true).unwrap().to_p2tr_address(Network::Bitcoin).unwrap().to_string()
+    );
+    assert_eq!(Err(WarpError::InvalidPublicKey), exponent.to_public_key(false).unwrap().to_p2tr_address(Network::Bitcoin));
+}
+
+#[test]
+fn test_test_network_keys_and_addresses() {
+    let vectors = vec![
+        // Secret exponent, uncompressed wif, uncompressed P2PKH, compressed wif, compressed P2PKH, P2SH-P2WPKH
+        (
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "91avARGdfge8E4tZfYLoxeJ5sGBdNJQH4kvjJoQFacbgwmaKkrx", "mtoKs9V381UAhUia3d7Vb9GNak8Qvmcsme",
+            "cMahea7zqjxrtgAbB7LSGbcQUr1uX1ojuat9jZodMN87JcbXMTcA", "mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r",
+            "2NAUYAHhujozruyzpsFRP63mbrdaU5wnEpN"
+        ),
+        (
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+            "92zXSPBMKaM7Zov5Jh3GN8fELqLQ67euugC4iDyEkUbf8iHo95K", "mrx5yAsvPypeby4geQaqKYYXmC2RoZ6aHT",
+            "cTnuM2tggzBmyp5G15izpGojtYLcuaNLXVyqNxFDgtoyJqKnduuk", "mhM4aMU59HNc4MypXFUNZkKWjHUqnce7ZM",
+            "2NAsQc4FLLiQJcMhL4aQced7Vjk1Fm1Gdnf"
+        )
+    ];
+    for tuple in vectors {
+        println!("Testing exponent [{}]", tuple.0);
+        let exponent: SecretExponent = tuple.0.parse().unwrap();
+        for network in &[Network::Testnet, Network::Signet, Network::Regtest] {
+            let uncompressed_wif = exponent.to_wif(false, *network).unwrap();
+            assert_eq!(tuple.1, uncompressed_wif.to_string());
+            assert_eq!(KeyFormat::Uncompressed, uncompressed_wif.format());
+            assert_eq!(tuple.2, uncompressed_wif.to_address().unwrap().to_string());
+            assert_eq!(tuple.2, exponent.to_public_key(false).unwrap().to_p2pkh_address(*network).to_string());
+
+            let compressed_wif = exponent.to_wif(true, *network).unwrap();
+            assert_eq!(tuple.3, compressed_wif.to_string());
+            assert_eq!(KeyFormat::Compressed, compressed_wif.format());
+            assert_eq!(tuple.4, compressed_wif.to_address().unwrap().to_string());
+            assert_eq!(tuple.5, exponent.to_public_key(true).unwrap().to_p2sh_p2wpkh_address(*network).unwrap().to_string());
+        }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_test_network_segwit_addresses() {
+    let vectors = vec![
+        // Network, P2WPKH address, P2TR address of secret exponent 1
+        (Network::Testnet, "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", "tb1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5ssk79hv2"),
+        (Network::Signet, "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", "tb1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5ssk79hv2"),
+        (Network::Regtest, "bcrt1qw508d6qejxtdg4y5r3zarvary0c5xw7kygt080", "bcrt1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5ssm803es")
+    ];
+    let public_key: PublicKey = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".parse().unwrap();
+    for tuple in vectors {
+        println!("Testing network [{}]", tuple.0);
+        assert_eq!(tuple.1, public_key.to_p2wpkh_address(tuple.0).unwrap().to_string());
+        assert_eq!(tuple.2, public_key.to_p2tr_address(tuple.0).unwrap().to_string());
+        assert_eq!(tuple.1, tuple.1.parse::<Address>().unwrap().to_string());
+        assert_eq!(tuple.2, tuple.2.parse::<Address>().unwrap().to_string());
+    }
+    assert_eq!(
+        "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+        public_key.to_p2pkh_address(Network::Bitcoin).to_string()
+    );
+}
+
+#[test]
+fn test_altcoin_keys_and_addresses() {
+    let vectors = vec![
+        // Coin, uncompressed wif, uncompressed P2PKH, compressed wif, compressed P2PKH, P2SH-P2WPKH of exponent 1
+        (
+            CoinProfile::LITECOIN,
+            "6u823ozcyt2rjPH8Z2ErsSXJB5PPQwK7VVTwwN4mxLBFrao69XQ", "LYWKqJhtPeGyBAw7WC8R3F7ovxtzAiubdM",
+            "T33ydQRKp4FCW5LCLLUB7deioUMoveiwekdwUwyfRDeGZm76aUjV", "LVuDpNCSSj6pQ7t9Pv6d6sUkLKoqDEVUnJ",
+            Some("MR8UQSBr5ULwWheBHznrHk2jxyxkHQu8vB")
+        ),
+        (
+            CoinProfile::DOGECOIN,
+            "6J8csdv3eDrnJcpSEb4shfjMh2JTiG9MKzC1Yfge4Y4GyUsjdM6", "DJRU7MLhcPwCTNRZ4e8gJzDebtG1H5M7pc",
+            "QNcdLVw8fHkixm6NNyN6nVwxKek4u7qrioRbQmjxac5TVoTtZuot", "DFpN6QqFfUm3gKNaxN6tNcab1FArL9cZLE",
+            None
+        ),
+        (
+            CoinProfile::DASH,
+            "7qYrzJZWqnyCWMYswFcqaRJypGdVceudXPSxmZKsngN7fyo7aAV", "XoyDQM3xGhFW5JqYBwTLckjqZ67Q3jZfAL",
+            "XBHddvWWiMu3nZhhpTXBQWJMmdz5JNKJD85b9fgKAckCT2coW3Y4", "XmN7PQYWKn5MJFna5fRYgP6mxT2F7xpekE",
+            None
+        ),
+        (
+            CoinProfile::BITCOIN_CASH,
+            "5HpHagT65TZzG1PH3CSu63k8DbpvD8s5ip4nEB3kEsreAnchuDf", "1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm",
+            "KwDiBf89QgGbjEhKnhXJuH7LrciVrZi3qYjgd9M7rFU73sVHnoWn", "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH",
+            None
+        )
+    ];
+    let exponent: SecretExponent = "0000000000000000000000000000000000000000000000000000000000000001".parse().unwrap();
+    for tuple in vectors {
+        println!("Testing coin [{}]", tuple.0);
+        let uncompressed_wif = exponent.to_wif(false, tuple.0).unwrap();
+        assert_eq!(tuple.1, uncompressed_wif.to_string());
+        assert_eq!(KeyFormat::Uncompressed, uncompressed_wif.format());
+        assert_eq!(tuple.2, uncompressed_wif.to_address().unwrap().to_string());
+        assert_eq!(tuple.1, tuple.1.parse::<PrivateKeyWif>().unwrap().to_string());
+
+        let compressed_wif = exponent.to_wif(true, tuple.0).unwrap();
+        assert_eq!(tuple.3, compressed_wif.to_string());
+        assert_eq!(KeyFormat::Compressed, compressed_wif.format());
+        assert_eq!(tuple.4, compressed_wif.to_address().unwrap().to_string());
+        assert_eq!(tuple.4, tuple.4.parse::<Address>().unwrap().to_string());
+
+        let public_key = exponent.to_public_key(true).unwrap();
+        assert_eq!(tuple.4, public_key.to_p2pkh_address(tuple.0).to_string());
+        match tuple.5 {
+            Some(address) => assert_eq!(address, public_key.to_p2sh_p2wpkh_address(tuple.0).unwrap().to_string()),
+            None => {
+                assert!(public_key.to_p2wpkh_address(tuple.0).is_err());
+                assert!(public_key.to_p2sh_p2wpkh_address(tuple.0).is_err());
+                assert!(public_key.to_p2tr_address(tuple.0).is_err());
+            }
+        }
+    }
+
+    let public_key = exponent.to_public_key(true).unwrap();
+    assert_eq!(
+        "ltc1qw508d6qejxtdg4y5r3zarvary0c5xw7kgmn4n9",
+        public_key.to_p2wpkh_address(CoinProfile::LITECOIN).unwrap().to_string()
+    );
+    assert_eq!(
+        "ltc1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5sszjagvq",
+        public_key.to_p2tr_address(CoinProfile::LITECOIN).unwrap().to_string()
+    );
+}
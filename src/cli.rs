@@ -0,0 +1,857 @@
+// Command line parsing for the binary. Kept dependency free on purpose, the flags are simple
+//      enough to walk through by hand.
+
+use rust_warpwallet::bitcoin::KeyFormat;
+use rust_warpwallet::coins::CoinProfile;
+use rust_warpwallet::combinator::Separator;
+use rust_warpwallet::hybrid::HybridSide;
+use rust_warpwallet::network::Network;
+use rust_warpwallet::params::WarpParams;
+use std::str::FromStr;
+
+pub const USAGE: &str = "Usage:
+    rust-warpwallet [search] [options]              derive the likeliest phrases of a --markov model,
+                                                    the lines of --wordlist files, a --mask, --combine,
+                                                    --recover, --typos, --template, --profile or --corpus
+                                                    phrases, looking for targets
+    rust-warpwallet derive <passphrase> [options]   derive the wallet of one passphrase
+    rust-warpwallet estimate [options]              memory and time one derivation takes here
+    rust-warpwallet index <addresses> <index>       build a hash160 index of an address list, one per line
+
+Options:
+    --salt <salt>           salt (usually an email address), default a@b.c
+    --salt-variants         search only: also derive every phrase with variants of --salt: case,
+                            whitespace, and Gmail dots, +tags and googlemail.com
+    --plus-tag <tag>        a +tag to try on the --salt-variants address, repeatable
+    --iterations <count>    most --markov phrases to search, default 50 without --threshold
+    --wordlist <file>       search only: passphrases to try, one per line, repeatable. Gzip files
+                            are read as is, - reads stdin
+    --rule <rule>           mangle every --wordlist entry with a hashcat style rule, e.g. 'c $1',
+                            repeatable. Candidates the rules make twice of one word are tried once
+    --rules <file>          rules to mangle with, one per line, repeatable
+    --mask <mask>           search only: every candidate of a hashcat style mask, in order.
+                            ?l ?u ?d ?s ?a ?h ?H ?b charsets, ?? for a literal ?
+    -1 .. -4 <charset>      custom charsets ?1 to ?4, e.g. -1 ?l?d or -1 aeiou
+    --increment             also try the shorter lengths of the mask, shortest first
+    --increment-min <n>     shortest length to try, default 1
+    --increment-max <n>     longest length to try, default the mask length
+    --hybrid <side>         with --wordlist and --mask: append or prepend every mask candidate to
+                            every word, e.g. --hybrid append --mask ?d?d?d?d for a year
+    --combine <file>        search only: phrases of one word of each --combine wordlist, in order,
+                            repeatable. The last wordlist fills any further words
+    --min-words <n>         fewest words of a --combine phrase, default the number of wordlists,
+                            or of a --corpus phrase, default 2
+    --max-words <n>         most words of a --combine phrase, default --min-words, or of a --corpus
+                            phrase, default 8
+    --recover <file>        search only: every order of the words remembered in <file>, one per line:
+                            word, one|of|these, ?wordlist, 3:pinned-third-word, first < second
+    --typos <phrase>        search only: <phrase> and the phrases a few typing slips away from it,
+                            likeliest first: caps lock, swapped, dropped or doubled characters,
+                            missing or extra spaces and neighbouring keys
+    --max-edits <n>         most slips in one --typos phrase, 1 or 2 (default)
+    --template <template>   search only: every phrase of a template, e.g. '{adj} {noun} {year:1990-2020}{sym?}'.
+                            {name} a --template-list, {name:A-B} numbers, {a|b} choices, {...?} optional
+    --template-list <name=file>
+                            the words of {name} in --template, repeatable
+    --profile <file>        search only: names, pets, places, phrases and dates of the owner, one
+                            per line like 'pet: Rex' or 'birthday: 1987-03-07', combined in a few
+                            cases with each other, with dates and with every --separator
+    --corpus <file>         search only: every run of --min-words to --max-words words of the
+                            sentences of a text file, e.g. lyrics or notes, as written and without
+                            punctuation, in a few cases, repeatable
+    --markov <file>         search only: train a character Markov model on the phrases of <file>,
+                            repeatable, and search the phrases it makes, likeliest first
+    --markov-order <n>      characters of context the --markov model looks at, 1 to 8, default 3
+    --min-length <n>        shortest --markov phrase, default 1
+    --max-length <n>        longest --markov phrase, default 16
+    --threshold <p>         stop at the first --markov phrase less likely than <p>, e.g. 1e-9
+    --separator <sep>       join --combine, --recover or --profile words with space (default, none and
+                            space for --profile), none, dash, capitalized (CorrectHorse) or any other
+                            literal text, repeatable
+    --skip <index>          start at this candidate index, to resume a --mask, --combine, --recover
+                            or --template search
+    --keyspace              print the number of candidates of the search and exit
+    --threads <count>       search only: derivations run in parallel, default 4. Each one needs
+                            the scrypt memory estimate reports
+    --scrypt-n <N>          scrypt CPU/memory cost, default 262144 (2^18)
+    --scrypt-r <r>          scrypt block size, default 8
+    --scrypt-p <p>          scrypt parallelism, default 1
+    --pbkdf2-c <c>          pbkdf2 iteration count, default 65536 (2^16)
+    --key-format <format>   uncompressed, compressed or both (default)
+    --network <network>     bitcoin (default), testnet, signet or regtest
+    --coin <coin>           bitcoin (default), litecoin, dogecoin, dash or bitcoin-cash
+    --eth-keystore <file>   derive only: also write an Ethereum keystore (v3 JSON) to <file>,
+                            encrypted with a password read from stdin
+    --target <address>      search only: address to look for, repeatable. P2PKH, P2SH-P2WPKH, P2WPKH,
+                            P2TR and Ethereum addresses of any supported coin or network
+    --targets <file>        search only: file with one target address per line, repeatable
+    --target-index <file>   search only: hash160 index built with the index command, repeatable
+    --bloom <bits>          search only: Bloom filter bits per indexed target, 10 gives ~1% false
+                            positives. Default none, every lookup is a binary search of the index
+
+Exit status:
+    0 done, nothing found    1 failure    2 usage error    3 search found a target";
+
+const DEFAULT_SALT: &str = "a@b.c";
+const DEFAULT_ITERATIONS: u64 = 50;
+const DEFAULT_THREADS: usize = 4;
+const DEFAULT_MAX_EDITS: usize = 2;
+const DEFAULT_CORPUS_MIN_WORDS: usize = 2;
+const DEFAULT_CORPUS_MAX_WORDS: usize = 8;
+const DEFAULT_MARKOV_ORDER: usize = 3;
+const DEFAULT_MIN_LENGTH: usize = 1;
+const DEFAULT_MAX_LENGTH: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Search,
+    Derive { passphrase: String },
+    Estimate,
+    Index { addresses: String, output: String }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub salt: String,
+    /// Search with the variants of the salt, not only the salt as given.
+    pub salt_variants: bool,
+    /// Tags given with --plus-tag, in order.
+    pub plus_tags: Vec<String>,
+    pub params: WarpParams,
+    pub key_formats: Vec<KeyFormat>,
+    /// The coin, or bitcoin network, keys and addresses are encoded for.
+    pub coin: CoinProfile,
+    pub eth_keystore: Option<String>,
+    /// Addresses given with --target, in order.
+    pub targets: Vec<String>,
+    /// Files given with --targets, in order.
+    pub target_files: Vec<String>,
+    /// Index files given with --target-index, in order.
+    pub target_indexes: Vec<String>,
+    pub bloom_bits: Option<usize>,
+    /// Wordlists given with --wordlist, in order.
+    pub wordlists: Vec<String>,
+    pub threads: usize,
+    /// Rules given with --rule, in order.
+    pub rules: Vec<String>,
+    /// Rule files given with --rules, in order.
+    pub rule_files: Vec<String>,
+    pub mask: Option<MaskOptions>,
+    /// Where --hybrid puts the mask on the --wordlist words.
+    pub hybrid: Option<HybridSide>,
+    pub combinator: Option<CombinatorOptions>,
+    /// Spec file given with --recover.
+    pub recover: Option<String>,
+    /// Phrase given with --typos.
+    pub typos: Option<String>,
+    pub max_edits: usize,
+    /// Template given with --template.
+    pub template: Option<String>,
+    /// (name, file) pairs given with --template-list, in order.
+    pub template_lists: Vec<(String, String)>,
+    pub markov: Option<MarkovOptions>,
+    /// Profile file given with --profile.
+    pub profile: Option<String>,
+    pub corpus: Option<CorpusOptions>,
+    /// Separators given with --separator, space, or none and space for --profile, when none was.
+    pub separators: Vec<Separator>,
+    /// Candidate index a --mask, --combine or --recover search starts at.
+    pub skip: u128,
+    /// Print the keyspace instead of searching it.
+    pub keyspace: bool
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MaskOptions {
+    pub mask: String,
+    /// -1 to -4, empty when not given.
+    pub custom_charsets: Vec<String>,
+    /// Length range of --increment, None for the full mask only.
+    pub lengths: Option<(Option<usize>, Option<usize>)>
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CombinatorOptions {
+    /// Wordlists given with --combine, in order.
+    pub wordlists: Vec<String>,
+    pub min_words: Option<usize>,
+    pub max_words: Option<usize>
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CorpusOptions {
+    /// Text files given with --corpus, in order.
+    pub files: Vec<String>,
+    pub min_words: usize,
+    pub max_words: usize
+}
+
+#[derive(Debug, PartialEq)]
+pub struct MarkovOptions {
+    /// Training wordlists given with --markov, in order.
+    pub wordlists: Vec<String>,
+    pub order: usize,
+    pub min_length: usize,
+    pub max_length: usize,
+    /// Lowest phrase probability to search, None to stop at the budget only.
+    pub threshold: Option<f64>,
+    /// Most phrases to search, None to stop at the threshold only.
+    pub budget: Option<u64>
+}
+
+fn parse_key_formats(value: Option<String>) -> Result<Vec<KeyFormat>, String> {
+    match value.as_ref().map(|format| format.as_str()) {
+        Some("both") => Ok(vec![KeyFormat::Uncompressed, KeyFormat::Compressed]),
+        Some(format) => Ok(vec![format.parse()?]),
+        None => Err("--key-format needs a value".to_owned())
+    }
+}
+
+fn parse_template_list(value: Option<String>) -> Result<(String, String), String> {
+    let value = value.ok_or_else(|| "--template-list needs a value".to_owned())?;
+    match value.find('=') {
+        Some(equals) if equals > 0 && equals + 1 < value.len() => Ok((value[..equals].to_owned(), value[equals + 1..].to_owned())),
+        _ => Err(format!("--template-list needs name=file, not [{}]", value))
+    }
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a value", flag))?;
+    value.parse().map_err(|_| format!("invalid value [{}] for {}", value, flag))
+}
+
+pub fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut salt = DEFAULT_SALT.to_owned();
+    let mut salt_variants = false;
+    let mut plus_tags: Vec<String> = vec![];
+    let mut params = WarpParams::keybase_v1();
+    let mut iterations: Option<u64> = None;
+    let mut key_formats = vec![KeyFormat::Uncompressed, KeyFormat::Compressed];
+    let mut network: Option<Network> = None;
+    let mut coin: Option<CoinProfile> = None;
+    let mut eth_keystore: Option<String> = None;
+    let mut targets: Vec<String> = vec![];
+    let mut target_files: Vec<String> = vec![];
+    let mut target_indexes: Vec<String> = vec![];
+    let mut bloom_bits: Option<usize> = None;
+    let mut wordlists: Vec<String> = vec![];
+    let mut threads = DEFAULT_THREADS;
+    let mut rules: Vec<String> = vec![];
+    let mut rule_files: Vec<String> = vec![];
+    let mut mask: Option<String> = None;
+    let mut custom_charsets = vec![String::new(); 4];
+    let mut increment = false;
+    let mut increment_min: Option<usize> = None;
+    let mut increment_max: Option<usize> = None;
+    let mut hybrid: Option<HybridSide> = None;
+    let mut combine: Vec<String> = vec![];
+    let mut separators: Vec<Separator> = vec![];
+    let mut min_words: Option<usize> = None;
+    let mut max_words: Option<usize> = None;
+    let mut recover: Option<String> = None;
+    let mut typos: Option<String> = None;
+    let mut max_edits: Option<usize> = None;
+    let mut template: Option<String> = None;
+    let mut template_lists: Vec<(String, String)> = vec![];
+    let mut markov: Vec<String> = vec![];
+    let mut profile: Option<String> = None;
+    let mut corpus: Vec<String> = vec![];
+    let mut markov_order: Option<usize> = None;
+    let mut min_length: Option<usize> = None;
+    let mut max_length: Option<usize> = None;
+    let mut threshold: Option<f64> = None;
+    let mut skip: Option<u128> = None;
+    let mut keyspace = false;
+    let mut positional: Vec<String> = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--salt" => salt = args.next().ok_or_else(|| "--salt needs a value".to_owned())?,
+            "--salt-variants" => salt_variants = true,
+            "--plus-tag" => plus_tags.push(parse_value(&arg, args.next())?),
+            "--iterations" => iterations = Some(parse_value(&arg, args.next())?),
+            "--scrypt-n" => params.scrypt_iterations = parse_value(&arg, args.next())?,
+            "--scrypt-r" => params.scrypt_mem_diff = parse_value(&arg, args.next())?,
+            "--scrypt-p" => params.scrypt_par_diff = parse_value(&arg, args.next())?,
+            "--pbkdf2-c" => params.pbkdf2_iterations = parse_value(&arg, args.next())?,
+            "--key-format" => key_formats = parse_key_formats(args.next())?,
+            "--network" => network = Some(parse_value(&arg, args.next())?),
+            "--coin" => coin = Some(parse_value(&arg, args.next())?),
+            "--eth-keystore" => eth_keystore = Some(parse_value(&arg, args.next())?),
+            "--target" => targets.push(parse_value(&arg, args.next())?),
+            "--targets" => target_files.push(parse_value(&arg, args.next())?),
+            "--target-index" => target_indexes.push(parse_value(&arg, args.next())?),
+            "--bloom" => bloom_bits = Some(parse_value(&arg, args.next())?),
+            "--wordlist" => wordlists.push(parse_value(&arg, args.next())?),
+            "--threads" => threads = parse_value(&arg, args.next())?,
+            "--rule" => rules.push(parse_value(&arg, args.next())?),
+            "--rules" => rule_files.push(parse_value(&arg, args.next())?),
+            "--mask" => mask = Some(parse_value(&arg, args.next())?),
+            "-1" | "-2" | "-3" | "-4" => {
+                let index = (arg.as_bytes()[1] - b'1') as usize;
+                custom_charsets[index] = parse_value(&arg, args.next())?;
+            },
+            "--increment" => increment = true,
+            "--increment-min" => increment_min = Some(parse_value(&arg, args.next())?),
+            "--increment-max" => increment_max = Some(parse_value(&arg, args.next())?),
+            "--hybrid" => hybrid = Some(parse_value(&arg, args.next())?),
+            "--combine" => combine.push(parse_value(&arg, args.next())?),
+            "--separator" => separators.push(parse_value(&arg, args.next())?),
+            "--min-words" => min_words = Some(parse_value(&arg, args.next())?),
+            "--max-words" => max_words = Some(parse_value(&arg, args.next())?),
+            "--recover" => recover = Some(parse_value(&arg, args.next())?),
+            "--typos" => typos = Some(parse_value(&arg, args.next())?),
+            "--max-edits" => max_edits = Some(parse_value(&arg, args.next())?),
+            "--template" => template = Some(parse_value(&arg, args.next())?),
+            "--template-list" => template_lists.push(parse_template_list(args.next())?),
+            "--corpus" => corpus.push(parse_value(&arg, args.next())?),
+            "--profile" => profile = Some(parse_value(&arg, args.next())?),
+            "--markov" => markov.push(parse_value(&arg, args.next())?),
+            "--markov-order" => markov_order = Some(parse_value(&arg, args.next())?),
+            "--min-length" => min_length = Some(parse_value(&arg, args.next())?),
+            "--max-length" => max_length = Some(parse_value(&arg, args.next())?),
+            "--threshold" => threshold = Some(parse_value(&arg, args.next())?),
+            "--skip" => skip = Some(parse_value(&arg, args.next())?),
+            "--keyspace" => keyspace = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ => positional.push(arg)
+        }
+    }
+
+    let command = match positional.first().map(|command| command.as_str()) {
+        None | Some("search") if positional.len() <= 1 => Command::Search,
+        Some("derive") if positional.len() == 2 => Command::Derive { passphrase: positional[1].clone() },
+        Some("derive") => return Err("derive needs exactly one passphrase (quote it if it has spaces)".to_owned()),
+        Some("estimate") if positional.len() == 1 => Command::Estimate,
+        Some("index") if positional.len() == 3 => Command::Index { addresses: positional[1].clone(), output: positional[2].clone() },
+        Some("index") => return Err("index needs an address list and an output file".to_owned()),
+        _ => return Err(format!("unknown command {}", positional.join(" ")))
+    };
+
+    let coin = match (coin, network) {
+        (Some(_), Some(_)) => return Err("--network and --coin can not be combined".to_owned()),
+        (Some(coin), None) => coin,
+        (None, network) => CoinProfile::from(network.unwrap_or_default())
+    };
+
+    match command {
+        Command::Derive { .. } => (),
+        _ if eth_keystore.is_some() => return Err("--eth-keystore only works with derive".to_owned()),
+        _ => ()
+    }
+
+    match command {
+        Command::Search => (),
+        _ if !targets.is_empty() || !target_files.is_empty() || !target_indexes.is_empty() || bloom_bits.is_some() =>
+            return Err("--target, --targets, --target-index and --bloom only work with search".to_owned()),
+        _ if !wordlists.is_empty() => return Err("--wordlist only works with search".to_owned()),
+        _ => ()
+    }
+
+    if salt_variants && !matches!(command, Command::Search) {
+        return Err("--salt-variants only works with search".to_owned());
+    }
+    if !plus_tags.is_empty() && !salt_variants {
+        return Err("--plus-tag needs --salt-variants".to_owned());
+    }
+
+    if bloom_bits.is_some() && target_indexes.is_empty() {
+        return Err("--bloom needs a --target-index".to_owned());
+    }
+    if threads == 0 {
+        return Err("--threads needs at least 1".to_owned());
+    }
+
+    if (!rules.is_empty() || !rule_files.is_empty()) && wordlists.is_empty() {
+        return Err("--rule and --rules need a --wordlist".to_owned());
+    }
+
+    if hybrid.is_some() && (wordlists.is_empty() || mask.is_none()) {
+        return Err("--hybrid needs a --wordlist and a --mask".to_owned());
+    }
+    if hybrid.is_some() && (!rules.is_empty() || !rule_files.is_empty()) {
+        return Err("--rule and --rules can not be combined with --hybrid".to_owned());
+    }
+    // A hybrid search counts as one source, the mask stands for it.
+    let sources = [
+        !wordlists.is_empty() && hybrid.is_none(), mask.is_some(), !combine.is_empty(), recover.is_some(), typos.is_some(),
+        template.is_some(), !markov.is_empty(), profile.is_some(), !corpus.is_empty()
+    ];
+    if sources.iter().filter(|given| **given).count() > 1 {
+        return Err("only one of --wordlist, --mask, --combine, --recover, --typos, --template, --markov, --profile and --corpus can be given".to_owned());
+    }
+    if !template_lists.is_empty() && template.is_none() {
+        return Err("--template-list needs a --template".to_owned());
+    }
+    if typos.is_some() && !matches!(command, Command::Search) {
+        return Err("--typos only works with search".to_owned());
+    }
+    if max_edits.is_some() && typos.is_none() {
+        return Err("--max-edits needs --typos".to_owned());
+    }
+    if (!markov.is_empty() || profile.is_some() || !corpus.is_empty()) && !matches!(command, Command::Search) {
+        return Err("--markov, --profile and --corpus only work with search".to_owned());
+    }
+    let indexed = mask.is_some() || !combine.is_empty() || recover.is_some() || template.is_some();
+    if indexed && !matches!(command, Command::Search) {
+        return Err("--mask, --combine, --recover and --template only work with search".to_owned());
+    }
+    if (skip.is_some() || keyspace) && !indexed {
+        return Err("--skip and --keyspace need a --mask, --combine, --recover or --template".to_owned());
+    }
+    if !separators.is_empty() && combine.is_empty() && recover.is_none() && profile.is_none() {
+        return Err("--separator needs a --combine, --recover or --profile".to_owned());
+    }
+
+    let mask_only = custom_charsets.iter().any(|charset| !charset.is_empty())
+        || increment || increment_min.is_some() || increment_max.is_some();
+    let mask = match mask {
+        Some(mask) => Some(MaskOptions {
+            mask,
+            custom_charsets,
+            lengths: if increment || increment_min.is_some() || increment_max.is_some() {
+                Some((increment_min, increment_max))
+            } else {
+                None
+            }
+        }),
+        None if mask_only => return Err("-1 to -4 and --increment need a --mask".to_owned()),
+        None => None
+    };
+
+    let markov = if !markov.is_empty() {
+        let min_length = min_length.unwrap_or(DEFAULT_MIN_LENGTH);
+        let max_length = max_length.unwrap_or_else(|| DEFAULT_MAX_LENGTH.max(min_length));
+        if min_length == 0 || min_length > max_length {
+            return Err("--min-length needs at least 1 and at most --max-length".to_owned());
+        }
+        if threshold.is_some_and(|threshold| !(threshold > 0.0 && threshold <= 1.0)) {
+            return Err("--threshold needs a probability above 0 and at most 1".to_owned());
+        }
+        // A threshold alone is a limit of its own, the default budget only stops an open ended search.
+        let budget = match (iterations, threshold) {
+            (Some(iterations), _) => Some(iterations),
+            (None, Some(_)) => None,
+            (None, None) => Some(DEFAULT_ITERATIONS)
+        };
+        Some(MarkovOptions { wordlists: markov, order: markov_order.unwrap_or(DEFAULT_MARKOV_ORDER), min_length, max_length, threshold, budget })
+    } else if markov_order.is_some() || min_length.is_some() || max_length.is_some() || threshold.is_some() || iterations.is_some() {
+        return Err("--iterations, --markov-order, --min-length, --max-length and --threshold need a --markov".to_owned());
+    } else {
+        None
+    };
+
+    let corpus = if !corpus.is_empty() {
+        let min_words = min_words.unwrap_or(DEFAULT_CORPUS_MIN_WORDS);
+        let max_words = max_words.unwrap_or_else(|| DEFAULT_CORPUS_MAX_WORDS.max(min_words));
+        if min_words == 0 || min_words > max_words {
+            return Err("--min-words needs at least 1 and at most --max-words".to_owned());
+        }
+        Some(CorpusOptions { files: corpus, min_words, max_words })
+    } else {
+        None
+    };
+
+    let combinator = if !combine.is_empty() {
+        Some(CombinatorOptions { wordlists: combine, min_words, max_words })
+    } else if corpus.is_none() && (min_words.is_some() || max_words.is_some()) {
+        return Err("--min-words and --max-words need a --combine or --corpus".to_owned());
+    } else {
+        None
+    };
+
+    Ok(Options {
+        command, salt, salt_variants, plus_tags, params, key_formats, coin, eth_keystore, targets, target_files, target_indexes, bloom_bits,
+        wordlists, threads, rules, rule_files, mask, hybrid, combinator, recover, typos,
+        max_edits: max_edits.unwrap_or(DEFAULT_MAX_EDITS),
+        template, template_lists, markov,
+        separators: if !separators.is_empty() {
+            separators
+        } else if profile.is_some() {
+            // Personal details are run together at least as often as they are spaced out.
+            vec![Separator::None, Separator::Space]
+        } else {
+            vec![Separator::Space]
+        },
+        profile, corpus,
+        skip: skip.unwrap_or(0), keyspace
+    })
+}
+
+#[cfg(test)]
+fn to_args(line: &[&str]) -> ::std::vec::IntoIter<String> {
+    line.iter().map(|arg| arg.to_string()).collect::<Vec<String>>().into_iter()
+}
+
+#[test]
+fn test_parse_defaults() {
+    let options = parse_args(to_args(&[])).unwrap();
+    assert_eq!(Command::Search, options.command);
+    assert_eq!("a@b.c", options.salt);
+    assert!(!options.salt_variants);
+    assert!(options.plus_tags.is_empty());
+    assert_eq!(WarpParams::keybase_v1(), options.params);
+    assert_eq!(vec![KeyFormat::Uncompressed, KeyFormat::Compressed], options.key_formats);
+    assert_eq!(CoinProfile::BITCOIN, options.coin);
+    assert_eq!(None, options.eth_keystore);
+    assert!(options.targets.is_empty());
+    assert!(options.target_files.is_empty());
+    assert!(options.target_indexes.is_empty());
+    assert_eq!(None, options.bloom_bits);
+    assert!(options.wordlists.is_empty());
+    assert_eq!(4, options.threads);
+    assert_eq!(None, options.mask);
+    assert_eq!(None, options.hybrid);
+    assert_eq!(None, options.combinator);
+    assert_eq!(None, options.recover);
+    assert_eq!(None, options.typos);
+    assert_eq!(2, options.max_edits);
+    assert_eq!(None, options.template);
+    assert!(options.template_lists.is_empty());
+    assert_eq!(None, options.markov);
+    assert_eq!(None, options.profile);
+    assert_eq!(None, options.corpus);
+    assert_eq!(vec![Separator::Space], options.separators);
+    assert_eq!(0, options.skip);
+    assert!(!options.keyspace);
+    assert!(options.rules.is_empty());
+    assert!(options.rule_files.is_empty());
+}
+
+#[test]
+fn test_parse_salt_variants() {
+    let options = parse_args(to_args(&["--salt", "j.doe@gmail.com", "--salt-variants", "--plus-tag", "btc", "--plus-tag", "+warp"])).unwrap();
+    assert_eq!("j.doe@gmail.com", options.salt);
+    assert!(options.salt_variants);
+    assert_eq!(vec!["btc", "+warp"], options.plus_tags);
+    assert!(parse_args(to_args(&["--plus-tag", "btc"])).is_err());
+    assert!(parse_args(to_args(&["--salt-variants", "--plus-tag"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--salt-variants"])).is_err());
+}
+
+#[test]
+fn test_parse_rules() {
+    let options = parse_args(to_args(&["--wordlist", "words.txt", "--rule", "c $1", "--rules", "best64.rule", "--rule", ":"])).unwrap();
+    assert_eq!(vec!["c $1", ":"], options.rules);
+    assert_eq!(vec!["best64.rule"], options.rule_files);
+    assert!(parse_args(to_args(&["--wordlist", "words.txt", "--rule"])).is_err());
+    assert!(parse_args(to_args(&["--rule", "c"])).is_err());
+    assert!(parse_args(to_args(&["--mask", "?d", "--rules", "best64.rule"])).is_err());
+}
+
+#[test]
+fn test_parse_mask() {
+    let options = parse_args(to_args(&["--mask", "?1?d?d", "-1", "?l?u", "-3", "xyz", "--skip", "1000"])).unwrap();
+    assert_eq!(Some(MaskOptions {
+        mask: "?1?d?d".to_owned(),
+        custom_charsets: vec!["?l?u".to_owned(), String::new(), "xyz".to_owned(), String::new()],
+        lengths: None
+    }), options.mask);
+    assert_eq!(1_000, options.skip);
+
+    let options = parse_args(to_args(&["--mask", "?d?d?d?d", "--increment", "--keyspace"])).unwrap();
+    assert_eq!(Some((None, None)), options.mask.unwrap().lengths);
+    assert!(options.keyspace);
+    let options = parse_args(to_args(&["--mask", "?d?d?d?d", "--increment-min", "2", "--increment-max", "3"])).unwrap();
+    assert_eq!(Some((Some(2), Some(3))), options.mask.unwrap().lengths);
+
+    assert!(parse_args(to_args(&["--mask"])).is_err());
+    assert!(parse_args(to_args(&["-1", "?d"])).is_err());
+    assert!(parse_args(to_args(&["--keyspace"])).is_err());
+    assert!(parse_args(to_args(&["--mask", "?d", "--skip", "-1"])).is_err());
+    assert!(parse_args(to_args(&["--mask", "?d", "--wordlist", "rockyou.txt"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--mask", "?d"])).is_err());
+}
+
+#[test]
+fn test_parse_hybrid() {
+    let options = parse_args(to_args(&["--wordlist", "phrases.txt", "--mask", "?d?d?d?d", "--hybrid", "append", "--skip", "10"])).unwrap();
+    assert_eq!(Some(HybridSide::Append), options.hybrid);
+    assert_eq!(vec!["phrases.txt"], options.wordlists);
+    assert_eq!("?d?d?d?d", options.mask.unwrap().mask);
+    assert_eq!(10, options.skip);
+    let options = parse_args(to_args(&["--hybrid", "prepend", "--mask", "?d?d?d?d", "--wordlist", "words.txt", "--keyspace"])).unwrap();
+    assert_eq!(Some(HybridSide::Prepend), options.hybrid);
+
+    assert!(parse_args(to_args(&["--wordlist", "words.txt", "--hybrid", "append"])).is_err());
+    assert!(parse_args(to_args(&["--mask", "?d", "--hybrid", "append"])).is_err());
+    assert!(parse_args(to_args(&["--wordlist", "words.txt", "--mask", "?d", "--hybrid", "inside"])).is_err());
+    assert!(parse_args(to_args(&["--wordlist", "words.txt", "--mask", "?d", "--hybrid", "append", "--rule", "c"])).is_err());
+    assert!(parse_args(to_args(&["--wordlist", "words.txt", "--mask", "?d", "--hybrid", "append", "--combine", "words.txt"])).is_err());
+}
+
+#[test]
+fn test_parse_combinator() {
+    let options = parse_args(to_args(&[
+        "--combine", "adjectives.txt", "--combine", "nouns.txt", "--separator", "capitalized", "--separator", "_",
+        "--max-words", "3", "--skip", "5"
+    ])).unwrap();
+    assert_eq!(Some(CombinatorOptions {
+        wordlists: vec!["adjectives.txt".to_owned(), "nouns.txt".to_owned()],
+        min_words: None,
+        max_words: Some(3)
+    }), options.combinator);
+    assert_eq!(vec![Separator::Capitalized, Separator::Literal("_".to_owned())], options.separators);
+    assert_eq!(5, options.skip);
+
+    let options = parse_args(to_args(&["--combine", "words.txt", "--keyspace"])).unwrap();
+    assert_eq!(vec![Separator::Space], options.separators);
+    assert!(options.keyspace);
+
+    assert!(parse_args(to_args(&["--separator", "dash"])).is_err());
+    assert!(parse_args(to_args(&["--min-words", "2"])).is_err());
+    assert!(parse_args(to_args(&["--combine", "words.txt", "--separator", ""])).is_err());
+    assert!(parse_args(to_args(&["--combine", "words.txt", "--max-words", "many"])).is_err());
+    assert!(parse_args(to_args(&["--combine", "words.txt", "--wordlist", "rockyou.txt"])).is_err());
+    assert!(parse_args(to_args(&["--combine", "words.txt", "--mask", "?d"])).is_err());
+    assert!(parse_args(to_args(&["--combine", "words.txt", "--rule", "c"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--combine", "words.txt"])).is_err());
+}
+
+#[test]
+fn test_parse_recover() {
+    let options = parse_args(to_args(&["--recover", "remembered.txt", "--separator", "none", "--keyspace"])).unwrap();
+    assert_eq!(Some("remembered.txt".to_owned()), options.recover);
+    assert_eq!(vec![Separator::None], options.separators);
+    assert!(options.keyspace);
+    assert!(parse_args(to_args(&["--recover"])).is_err());
+    assert!(parse_args(to_args(&["--recover", "remembered.txt", "--combine", "words.txt"])).is_err());
+    assert!(parse_args(to_args(&["--recover", "remembered.txt", "--max-words", "3"])).is_err());
+    assert!(parse_args(to_args(&["estimate", "--recover", "remembered.txt"])).is_err());
+}
+
+#[test]
+fn test_parse_template() {
+    let options = parse_args(to_args(&[
+        "--template", "{adj} {noun} {year:1990-2020}", "--template-list", "adj=adjectives.txt", "--template-list", "noun=a=b.txt",
+        "--skip", "3"
+    ])).unwrap();
+    assert_eq!(Some("{adj} {noun} {year:1990-2020}".to_owned()), options.template);
+    assert_eq!(vec![
+        ("adj".to_owned(), "adjectives.txt".to_owned()),
+        ("noun".to_owned(), "a=b.txt".to_owned())
+    ], options.template_lists);
+    assert_eq!(3, options.skip);
+
+    let vectors: Vec<Vec<&str>> = vec![
+        vec!["--template"],
+        vec!["--template", "{n:1-9}", "--template-list"],
+        vec!["--template", "{n:1-9}", "--template-list", "adjectives.txt"],
+        vec!["--template", "{n:1-9}", "--template-list", "=adjectives.txt"],
+        vec!["--template", "{n:1-9}", "--template-list", "adj="],
+        vec!["--template-list", "adj=adjectives.txt"],
+        vec!["--template", "{n:1-9}", "--mask", "?d"],
+        vec!["derive", "Dutch Power Cows", "--template", "{n:1-9}"]
+    ];
+    for vector in vectors {
+        println!("Testing arguments {:?}", vector);
+        assert!(parse_args(to_args(&vector)).is_err());
+    }
+}
+
+#[test]
+fn test_parse_profile() {
+    let options = parse_args(to_args(&["--profile", "lucy.txt"])).unwrap();
+    assert_eq!(Some("lucy.txt".to_owned()), options.profile);
+    assert_eq!(vec![Separator::None, Separator::Space], options.separators);
+    let options = parse_args(to_args(&["--profile", "lucy.txt", "--separator", "_"])).unwrap();
+    assert_eq!(vec![Separator::Literal("_".to_owned())], options.separators);
+    assert!(parse_args(to_args(&["--profile"])).is_err());
+    assert!(parse_args(to_args(&["--profile", "lucy.txt", "--wordlist", "rockyou.txt"])).is_err());
+    assert!(parse_args(to_args(&["--profile", "lucy.txt", "--keyspace"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--profile", "lucy.txt"])).is_err());
+}
+
+#[test]
+fn test_parse_corpus() {
+    let options = parse_args(to_args(&["--corpus", "lyrics.txt", "--corpus", "notes.txt.gz"])).unwrap();
+    assert_eq!(Some(CorpusOptions { files: vec!["lyrics.txt".to_owned(), "notes.txt.gz".to_owned()], min_words: 2, max_words: 8 }), options.corpus);
+    let options = parse_args(to_args(&["--corpus", "lyrics.txt", "--min-words", "10"])).unwrap();
+    assert_eq!(Some(CorpusOptions { files: vec!["lyrics.txt".to_owned()], min_words: 10, max_words: 10 }), options.corpus);
+    assert_eq!(None, options.combinator);
+
+    let vectors: Vec<Vec<&str>> = vec![
+        vec!["--corpus"],
+        vec!["--corpus", "lyrics.txt", "--min-words", "0"],
+        vec!["--corpus", "lyrics.txt", "--min-words", "4", "--max-words", "3"],
+        vec!["--corpus", "lyrics.txt", "--combine", "words.txt"],
+        vec!["--corpus", "lyrics.txt", "--separator", "dash"],
+        vec!["--corpus", "lyrics.txt", "--skip", "10"],
+        vec!["estimate", "--corpus", "lyrics.txt"]
+    ];
+    for vector in vectors {
+        println!("Testing arguments {:?}", vector);
+        assert!(parse_args(to_args(&vector)).is_err());
+    }
+}
+
+#[test]
+fn test_parse_typos() {
+    let options = parse_args(to_args(&["--typos", "Dutch Power Cows", "--max-edits", "1"])).unwrap();
+    assert_eq!(Some("Dutch Power Cows".to_owned()), options.typos);
+    assert_eq!(1, options.max_edits);
+    assert!(parse_args(to_args(&["--typos"])).is_err());
+    assert!(parse_args(to_args(&["--max-edits", "1"])).is_err());
+    assert!(parse_args(to_args(&["--typos", "Dutch Power Cows", "--wordlist", "rockyou.txt"])).is_err());
+    assert!(parse_args(to_args(&["--typos", "Dutch Power Cows", "--keyspace"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--typos", "Dutch Power Cows"])).is_err());
+}
+
+#[test]
+fn test_parse_markov() {
+    let options = parse_args(to_args(&["--markov", "rockyou.txt", "--markov", "-", "--iterations", "1000", "--max-length", "10"])).unwrap();
+    assert_eq!(Command::Search, options.command);
+    assert_eq!(Some(MarkovOptions {
+        wordlists: vec!["rockyou.txt".to_owned(), "-".to_owned()],
+        order: 3,
+        min_length: 1,
+        max_length: 10,
+        threshold: None,
+        budget: Some(1_000)
+    }), options.markov);
+    let options = parse_args(to_args(&["--markov", "rockyou.txt"])).unwrap();
+    assert_eq!(Some(50), options.markov.unwrap().budget);
+    let options = parse_args(to_args(&["--markov", "words.txt", "--markov-order", "2", "--min-length", "20", "--threshold", "1e-9"])).unwrap();
+    assert_eq!(Some(MarkovOptions {
+        wordlists: vec!["words.txt".to_owned()],
+        order: 2,
+        min_length: 20,
+        max_length: 20,
+        threshold: Some(1e-9),
+        budget: None
+    }), options.markov);
+    let options = parse_args(to_args(&["--markov", "words.txt", "--threshold", "1e-9", "--iterations", "100"])).unwrap();
+    let markov = options.markov.unwrap();
+    assert_eq!((Some(1e-9), Some(100)), (markov.threshold, markov.budget));
+
+    let vectors: Vec<Vec<&str>> = vec![
+        vec!["--markov"],
+        vec!["--markov-order", "2"],
+        vec!["--threshold", "0.5"],
+        vec!["--iterations", "100"],
+        vec!["--wordlist", "rockyou.txt", "--iterations", "100"],
+        vec!["--markov", "words.txt", "--iterations", "-1"],
+        vec!["--markov", "words.txt", "--min-length", "0"],
+        vec!["--markov", "words.txt", "--min-length", "8", "--max-length", "6"],
+        vec!["--markov", "words.txt", "--threshold", "0"],
+        vec!["--markov", "words.txt", "--threshold", "2"],
+        vec!["--markov", "words.txt", "--wordlist", "rockyou.txt"],
+        vec!["--markov", "words.txt", "--keyspace"],
+        vec!["derive", "Dutch Power Cows", "--markov", "words.txt"]
+    ];
+    for vector in vectors {
+        println!("Testing arguments {:?}", vector);
+        assert!(parse_args(to_args(&vector)).is_err());
+    }
+}
+
+#[test]
+fn test_parse_wordlists() {
+    let options = parse_args(to_args(&["--wordlist", "rockyou.txt.gz", "--wordlist", "-", "--threads", "2"])).unwrap();
+    assert_eq!(vec!["rockyou.txt.gz", "-"], options.wordlists);
+    assert_eq!(2, options.threads);
+    assert!(parse_args(to_args(&["--wordlist"])).is_err());
+    assert!(parse_args(to_args(&["--threads", "0"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--wordlist", "rockyou.txt"])).is_err());
+}
+
+#[test]
+fn test_parse_targets() {
+    let options = parse_args(to_args(&[
+        "--target", "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA", "--targets", "addresses.txt",
+        "--target", "bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz"
+    ])).unwrap();
+    assert_eq!(vec!["1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA", "bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz"], options.targets);
+    assert_eq!(vec!["addresses.txt"], options.target_files);
+    assert!(parse_args(to_args(&["--target"])).is_err());
+    assert!(parse_args(to_args(&["--targets"])).is_err());
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--target", "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"])).is_err());
+}
+
+#[test]
+fn test_parse_eth_keystore() {
+    let options = parse_args(to_args(&["derive", "Dutch Power Cows", "--eth-keystore", "wallet.json"])).unwrap();
+    assert_eq!(Some("wallet.json".to_owned()), options.eth_keystore);
+    assert!(parse_args(to_args(&["derive", "Dutch Power Cows", "--eth-keystore"])).is_err());
+    assert!(parse_args(to_args(&["search", "--eth-keystore", "wallet.json"])).is_err());
+}
+
+#[test]
+fn test_parse_network() {
+    let options = parse_args(to_args(&["derive", "Dutch Power Cows", "--network", "testnet"])).unwrap();
+    assert_eq!(CoinProfile::from(Network::Testnet), options.coin);
+    let options = parse_args(to_args(&["--network", "regtest"])).unwrap();
+    assert_eq!(CoinProfile::from(Network::Regtest), options.coin);
+    assert!(parse_args(to_args(&["--network", "dogecoin"])).is_err());
+    assert!(parse_args(to_args(&["--network"])).is_err());
+}
+
+#[test]
+fn test_parse_coin() {
+    let options = parse_args(to_args(&["--coin", "dogecoin"])).unwrap();
+    assert_eq!(CoinProfile::DOGECOIN, options.coin);
+    let options = parse_args(to_args(&["derive", "Dutch Power Cows", "--coin", "litecoin"])).unwrap();
+    assert_eq!(CoinProfile::LITECOIN, options.coin);
+    assert!(parse_args(to_args(&["--coin", "monero"])).is_err());
+    assert!(parse_args(to_args(&["--coin", "dash", "--network", "testnet"])).is_err());
+}
+
+#[test]
+fn test_parse_key_format() {
+    let options = parse_args(to_args(&["--key-format", "compressed"])).unwrap();
+    assert_eq!(vec![KeyFormat::Compressed], options.key_formats);
+    let options = parse_args(to_args(&["--key-format", "uncompressed"])).unwrap();
+    assert_eq!(vec![KeyFormat::Uncompressed], options.key_formats);
+    assert!(parse_args(to_args(&["--key-format", "zipped"])).is_err());
+}
+
+#[test]
+fn test_parse_derive_with_params() {
+    let options = parse_args(to_args(&[
+        "derive", "Dutch Power Cows", "--salt", "me@example.com", "--scrypt-n", "1024", "--pbkdf2-c", "16"
+    ])).unwrap();
+    assert_eq!(Command::Derive { passphrase: "Dutch Power Cows".to_owned() }, options.command);
+    assert_eq!("me@example.com", options.salt);
+    assert_eq!(1_024, options.params.scrypt_iterations);
+    assert_eq!(16, options.params.pbkdf2_iterations);
+    assert_eq!(8, options.params.scrypt_mem_diff);
+}
+
+#[test]
+fn test_parse_index() {
+    let options = parse_args(to_args(&["index", "funded.txt", "funded.h160"])).unwrap();
+    assert_eq!(Command::Index { addresses: "funded.txt".to_owned(), output: "funded.h160".to_owned() }, options.command);
+    assert!(parse_args(to_args(&["index", "funded.txt"])).is_err());
+
+    let options = parse_args(to_args(&["--target-index", "funded.h160", "--bloom", "12"])).unwrap();
+    assert_eq!(vec!["funded.h160"], options.target_indexes);
+    assert_eq!(Some(12), options.bloom_bits);
+    assert!(parse_args(to_args(&["--bloom", "12"])).is_err());
+    assert!(parse_args(to_args(&["--target-index", "funded.h160", "--bloom", "many"])).is_err());
+    assert!(parse_args(to_args(&["estimate", "--target-index", "funded.h160"])).is_err());
+}
+
+#[test]
+fn test_parse_estimate() {
+    let options = parse_args(to_args(&["estimate", "--scrypt-n", "16384"])).unwrap();
+    assert_eq!(Command::Estimate, options.command);
+    assert_eq!(16_384, options.params.scrypt_iterations);
+}
+
+#[test]
+fn test_parse_errors() {
+    let vectors: Vec<Vec<&str>> = vec![
+        vec!["--scrypt-n"],
+        vec!["--scrypt-n", "lots"],
+        vec!["--unknown"],
+        vec!["derive"],
+        vec!["derive", "too", "many"],
+        vec!["estimate", "now"],
+        vec!["fly"]
+    ];
+    for vector in vectors {
+        println!("Testing arguments {:?}", vector);
+        assert!(parse_args(to_args(&vector)).is_err());
+    }
+}
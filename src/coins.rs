@@ -0,0 +1,159 @@
+// Version bytes and segwit hrps of secp256k1 chains that share bitcoin's key and address formats.
+// A warp exponent is a plain secp256k1 key, so only the encoding differs between these coins.
+
+use network::Network;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CoinProfile {
+    pub name: &'static str,
+    /// Version byte of P2PKH addresses.
+    pub p2pkh_version: u8,
+    /// Version byte of P2SH addresses.
+    pub p2sh_version: u8,
+    /// First byte of a WIF payload.
+    pub wif_version: u8,
+    /// Human readable part of segwit addresses, None for chains without segwit.
+    pub bech32_hrp: Option<&'static str>
+}
+
+impl CoinProfile {
+    pub const BITCOIN: CoinProfile = CoinProfile {
+        name: "bitcoin",
+        p2pkh_version: 0x00,
+        p2sh_version: 0x05,
+        wif_version: 0x80,
+        bech32_hrp: Some("bc")
+    };
+
+    pub const LITECOIN: CoinProfile = CoinProfile {
+        name: "litecoin",
+        p2pkh_version: 0x30,
+        // M... addresses, Litecoin used to share bitcoin's 0x05 (3...).
+        p2sh_version: 0x32,
+        wif_version: 0xb0,
+        bech32_hrp: Some("ltc")
+    };
+
+    pub const DOGECOIN: CoinProfile = CoinProfile {
+        name: "dogecoin",
+        p2pkh_version: 0x1e,
+        p2sh_version: 0x16,
+        wif_version: 0x9e,
+        bech32_hrp: None
+    };
+
+    pub const DASH: CoinProfile = CoinProfile {
+        name: "dash",
+        p2pkh_version: 0x4c,
+        p2sh_version: 0x10,
+        wif_version: 0xcc,
+        bech32_hrp: None
+    };
+
+    /// Bitcoin Cash with legacy (bitcoin style) addresses, not cashaddr.
+    pub const BITCOIN_CASH: CoinProfile = CoinProfile {
+        name: "bitcoin-cash",
+        p2pkh_version: 0x00,
+        p2sh_version: 0x05,
+        wif_version: 0x80,
+        bech32_hrp: None
+    };
+
+    pub const BUILT_IN: [CoinProfile; 5] = [
+        CoinProfile::BITCOIN,
+        CoinProfile::LITECOIN,
+        CoinProfile::DOGECOIN,
+        CoinProfile::DASH,
+        CoinProfile::BITCOIN_CASH
+    ];
+
+    /**
+     * The built in coins followed by the bitcoin test networks.
+     */
+    pub fn all() -> Vec<CoinProfile> {
+        let networks = Network::ALL.iter().skip(1).map(|network| CoinProfile::from(*network));
+        CoinProfile::BUILT_IN.iter().cloned().chain(networks).collect()
+    }
+
+    /**
+     * The first known profile with this WIF version byte. Bitcoin Cash shares bitcoin's and the
+     * test networks share testnet's, which does not matter as their P2PKH versions are the same too.
+     */
+    pub fn from_wif_version(version: u8) -> Option<CoinProfile> {
+        CoinProfile::all().into_iter().find(|coin| coin.wif_version == version)
+    }
+}
+
+impl From<Network> for CoinProfile {
+    fn from(network: Network) -> CoinProfile {
+        match network {
+            Network::Bitcoin => CoinProfile::BITCOIN,
+            _ => CoinProfile {
+                name: match network {
+                    Network::Testnet => "testnet",
+                    Network::Signet => "signet",
+                    _ => "regtest"
+                },
+                p2pkh_version: network.p2pkh_version(),
+                p2sh_version: network.p2sh_version(),
+                wif_version: network.wif_version(),
+                bech32_hrp: Some(network.bech32_hrp())
+            }
+        }
+    }
+}
+
+impl Default for CoinProfile {
+    fn default() -> CoinProfile {
+        CoinProfile::BITCOIN
+    }
+}
+
+impl FromStr for CoinProfile {
+    type Err = String;
+
+    /**
+     * Built in coins by name, plus the bitcoin networks ("testnet", "regtest", ...).
+     */
+    fn from_str(name: &str) -> Result<CoinProfile, String> {
+        if let Some(coin) = CoinProfile::BUILT_IN.iter().find(|coin| coin.name == name) {
+            return Ok(*coin);
+        }
+        name.parse::<Network>().map(CoinProfile::from).map_err(|_| format!("unknown coin {}", name))
+    }
+}
+
+impl fmt::Display for CoinProfile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+#[test]
+fn test_coin_profiles_from_str() {
+    for coin in &CoinProfile::all() {
+        println!("Testing coin [{}]", coin);
+        assert_eq!(Ok(*coin), coin.to_string().parse());
+    }
+    assert_eq!(Ok(CoinProfile::BITCOIN), "mainnet".parse());
+    assert_eq!(CoinProfile::BITCOIN, CoinProfile::default());
+    assert!("monero".parse::<CoinProfile>().is_err());
+}
+
+#[test]
+fn test_network_profiles() {
+    let regtest = CoinProfile::from(Network::Regtest);
+    assert_eq!("regtest", regtest.name);
+    assert_eq!(0x6f, regtest.p2pkh_version);
+    assert_eq!(0xc4, regtest.p2sh_version);
+    assert_eq!(0xef, regtest.wif_version);
+    assert_eq!(Some("bcrt"), regtest.bech32_hrp);
+    assert_eq!(CoinProfile::BITCOIN, CoinProfile::from(Network::Bitcoin));
+
+    assert_eq!(Some(CoinProfile::BITCOIN), CoinProfile::from_wif_version(0x80));
+    assert_eq!(Some(CoinProfile::from(Network::Testnet)), CoinProfile::from_wif_version(0xef));
+    assert_eq!(Some(CoinProfile::DOGECOIN), CoinProfile::from_wif_version(0x9e));
+    assert_eq!(None, CoinProfile::from_wif_version(0x01));
+}
@@ -0,0 +1,310 @@
+// Multi-word passphrases made of words from two or more wordlists, "correct horse battery staple".
+// A phrase of n words takes its i-th word from the i-th list, the last list filling any further
+// slots, so a single list gives every n-word phrase of that list. Each phrase comes once per separator.
+// Phrases are enumerated in a fixed order (fewest words first, last word turning fastest, then the
+// separators) so the keyspace is exact and a search can start at any index. Separators do not change
+// a one-word phrase, so it comes once, and once more capitalized if capitalized is a separator.
+
+use error::WarpError;
+use keyspace::{self, Keyspace};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use wordlist::Wordlist;
+
+/// How the words of a phrase are joined.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Separator {
+    Space,
+    None,
+    Dash,
+    /// No separator, every word capitalized: CorrectHorseBatteryStaple.
+    Capitalized,
+    Literal(String)
+}
+
+impl Separator {
+    /**
+     * Joins the words of one phrase.
+     */
+    pub fn join(&self, words: &[&[u8]]) -> Vec<u8> {
+        let glue: &[u8] = match *self {
+            Separator::Space => b" ",
+            Separator::Dash => b"-",
+            Separator::Literal(ref glue) => glue.as_bytes(),
+            Separator::None | Separator::Capitalized => b""
+        };
+        let mut phrase = Vec::with_capacity(words.iter().map(|word| word.len() + glue.len()).sum());
+        for (index, word) in words.iter().enumerate() {
+            if index > 0 {
+                phrase.extend_from_slice(glue);
+            }
+            let start = phrase.len();
+            phrase.extend_from_slice(word);
+            if *self == Separator::Capitalized {
+                if let Some(first) = phrase.get_mut(start) {
+                    first.make_ascii_uppercase();
+                }
+            }
+        }
+        phrase
+    }
+}
+
+impl FromStr for Separator {
+    type Err = WarpError;
+
+    /**
+     * space, none, dash and capitalized, anything else is used literally ("_", ". ", ...).
+     */
+    fn from_str(separator: &str) -> Result<Separator, WarpError> {
+        match separator {
+            "space" => Ok(Separator::Space),
+            "none" => Ok(Separator::None),
+            "dash" => Ok(Separator::Dash),
+            "capitalized" => Ok(Separator::Capitalized),
+            "" => Err(WarpError::InvalidPattern("use none for an empty separator".to_owned())),
+            literal => Ok(Separator::Literal(literal.to_owned()))
+        }
+    }
+}
+
+impl fmt::Display for Separator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Separator::Space => write!(f, "space"),
+            Separator::None => write!(f, "none"),
+            Separator::Dash => write!(f, "dash"),
+            Separator::Capitalized => write!(f, "capitalized"),
+            Separator::Literal(ref literal) => write!(f, "{}", literal)
+        }
+    }
+}
+
+/// The wordlists, loaded, and the separators and word counts to enumerate.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Combinator {
+    lists: Vec<Vec<Vec<u8>>>,
+    separators: Vec<Separator>,
+    /// The separators that make distinct one-word phrases: none, and capitalized if it was given.
+    one_word: Vec<Separator>,
+    min_words: usize,
+    max_words: usize
+}
+
+impl Combinator {
+    /**
+     * Phrases of as many words as there are lists, at least two, joined by spaces.
+     */
+    pub fn new(lists: Vec<Vec<Vec<u8>>>) -> Result<Combinator, WarpError> {
+        if lists.is_empty() || lists.iter().any(|list| list.is_empty()) {
+            return Err(WarpError::InvalidPattern("every combinator wordlist needs at least one word".to_owned()));
+        }
+        let words = lists.len().max(2);
+        Combinator::checked(Combinator { lists, separators: vec![Separator::Space], one_word: vec![], min_words: words, max_words: words })
+    }
+
+    /**
+     * Reads every wordlist into memory, each phrase needs random access to all of them.
+     */
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Combinator, WarpError> {
+        let lists = paths.iter()
+            .map(|path| Wordlist::open(vec![path.as_ref()]).collect::<Result<Vec<Vec<u8>>, WarpError>>())
+            .collect::<Result<Vec<Vec<Vec<u8>>>, WarpError>>()?;
+        Combinator::new(lists)
+    }
+
+    /**
+     * Every phrase of two or more words is tried once with each separator, in the order given.
+     */
+    pub fn with_separators(self, separators: Vec<Separator>) -> Result<Combinator, WarpError> {
+        if separators.is_empty() {
+            return Err(WarpError::InvalidPattern("at least one separator is needed".to_owned()));
+        }
+        Combinator::checked(Combinator { separators, ..self })
+    }
+
+    /**
+     * Enumerates phrases of min to max words, fewer words first.
+     */
+    pub fn with_word_counts(self, min_words: usize, max_words: usize) -> Result<Combinator, WarpError> {
+        if min_words == 0 || min_words > max_words {
+            return Err(WarpError::InvalidPattern(format!("can not make phrases of {} to {} words", min_words, max_words)));
+        }
+        Combinator::checked(Combinator { min_words, max_words, ..self })
+    }
+
+    fn checked(mut combinator: Combinator) -> Result<Combinator, WarpError> {
+        combinator.one_word = vec![];
+        for separator in &combinator.separators {
+            let rendering = if *separator == Separator::Capitalized { Separator::Capitalized } else { Separator::None };
+            if !combinator.one_word.contains(&rendering) {
+                combinator.one_word.push(rendering);
+            }
+        }
+        let mut total: u128 = 0;
+        for words in combinator.min_words..=combinator.max_words {
+            total = combinator.count_keyspace(words).and_then(|keyspace| total.checked_add(keyspace))
+                .ok_or_else(keyspace::overflow)?;
+        }
+        Ok(combinator)
+    }
+
+    fn list(&self, slot: usize) -> &[Vec<u8>] {
+        &self.lists[slot.min(self.lists.len() - 1)]
+    }
+
+    fn separators(&self, words: usize) -> &[Separator] {
+        if words == 1 { &self.one_word } else { &self.separators }
+    }
+
+    /**
+     * Phrases of exactly `words` words, every separator included.
+     */
+    fn count_keyspace(&self, words: usize) -> Option<u128> {
+        (0..words).try_fold(self.separators(words).len() as u128, |total, slot| total.checked_mul(self.list(slot).len() as u128))
+    }
+
+    /**
+     * The word index of every slot and the separator index of phrase number `index`.
+     */
+    fn digits(&self, mut index: u128) -> Option<(Vec<usize>, usize)> {
+        for words in self.min_words..=self.max_words {
+            let keyspace = self.count_keyspace(words).expect("checked when the combinator was built");
+            if index >= keyspace {
+                index -= keyspace;
+                continue;
+            }
+            let separators = self.separators(words).len() as u128;
+            let separator = (index % separators) as usize;
+            index /= separators;
+            let mut slots = vec![0; words];
+            for slot in (0..words).rev() {
+                let radix = self.list(slot).len() as u128;
+                slots[slot] = (index % radix) as usize;
+                index /= radix;
+            }
+            return Some((slots, separator));
+        }
+        None
+    }
+
+    fn render(&self, slots: &[usize], separator: usize) -> Vec<u8> {
+        let words: Vec<&[u8]> = slots.iter().enumerate().map(|(slot, word)| &self.list(slot)[*word][..]).collect();
+        self.separators(slots.len())[separator].join(&words)
+    }
+}
+
+impl Keyspace for Combinator {
+    /**
+     * Over all word counts and separators.
+     */
+    fn keyspace(&self) -> u128 {
+        (self.min_words..=self.max_words)
+            .map(|words| self.count_keyspace(words).expect("checked when the combinator was built"))
+            .sum()
+    }
+
+    fn candidate(&self, index: u128) -> Option<Vec<u8>> {
+        self.digits(index).map(|(slots, separator)| self.render(&slots, separator))
+    }
+
+    fn iter_from(&self, start: u128) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        Box::new(CombinatorIter { combinator: self, digits: self.digits(start) })
+    }
+}
+
+/// Walks a combinator's keyspace without recomputing each phrase from its index.
+struct CombinatorIter<'a> {
+    combinator: &'a Combinator,
+    digits: Option<(Vec<usize>, usize)>
+}
+
+impl<'a> Iterator for CombinatorIter<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let combinator = self.combinator;
+        let (ref mut slots, ref mut separator) = *self.digits.as_mut()?;
+        let candidate = combinator.render(slots, *separator);
+
+        *separator += 1;
+        if *separator < combinator.separators(slots.len()).len() {
+            return Some(candidate);
+        }
+        *separator = 0;
+        let mut slot = slots.len();
+        loop {
+            if slot == 0 {
+                // Every slot wrapped, go on with one more word.
+                let words = slots.len() + 1;
+                self.digits = if words <= combinator.max_words { Some((vec![0; words], 0)) } else { None };
+                break;
+            }
+            slot -= 1;
+            slots[slot] += 1;
+            if slots[slot] < combinator.list(slot).len() {
+                break;
+            }
+            slots[slot] = 0;
+        }
+        Some(candidate)
+    }
+}
+
+#[cfg(test)]
+fn test_lists(lists: &[&[&str]]) -> Vec<Vec<Vec<u8>>> {
+    lists.iter().map(|list| list.iter().map(|word| word.as_bytes().to_vec()).collect()).collect()
+}
+
+#[test]
+fn test_combine_two_lists() {
+    let combinator = Combinator::new(test_lists(&[&["correct", "horse"], &["battery", "staple", "warp"]])).unwrap()
+        .with_separators(vec![Separator::Space, Separator::Capitalized]).unwrap();
+    assert_eq!(2 * 3 * 2, combinator.keyspace());
+    let candidates = keyspace::as_strings(keyspace::assert_numbering(&combinator));
+    assert_eq!(vec![
+        "correct battery", "CorrectBattery", "correct staple", "CorrectStaple", "correct warp", "CorrectWarp",
+        "horse battery", "HorseBattery", "horse staple", "HorseStaple", "horse warp", "HorseWarp"
+    ], candidates);
+    assert_eq!(vec!["horse warp", "HorseWarp"], keyspace::as_strings(combinator.iter_from(10)));
+}
+
+#[test]
+fn test_combine_word_counts() {
+    let combinator = Combinator::new(test_lists(&[&["a", "b", "c"]])).unwrap()
+        .with_word_counts(1, 3).unwrap()
+        .with_separators(vec![Separator::Dash, "_".parse().unwrap(), Separator::None]).unwrap();
+    assert_eq!(3 + (9 + 27) * 3, combinator.keyspace());
+    let candidates = keyspace::as_strings(keyspace::assert_numbering(&combinator));
+    assert_eq!(vec!["a", "b", "c", "a-a", "a_a", "aa"], candidates[..6].to_vec());
+    assert_eq!("c_c_c", candidates[candidates.len() - 2]);
+    keyspace::assert_distinct(&candidates);
+
+    // One word is only changed by capitalized.
+    let combinator = Combinator::new(test_lists(&[&["a", "b"]])).unwrap()
+        .with_word_counts(1, 2).unwrap()
+        .with_separators(vec![Separator::Space, Separator::Capitalized, Separator::Dash]).unwrap();
+    assert_eq!(2 * 2 + 4 * 3, combinator.keyspace());
+    let candidates = keyspace::as_strings(keyspace::assert_numbering(&combinator));
+    assert_eq!(vec!["a", "A", "b", "B", "a a", "AA", "a-a"], candidates[..7].to_vec());
+
+    // The last list fills the slots past the number of lists.
+    let combinator = Combinator::new(test_lists(&[&["my"], &["red", "blue"]])).unwrap().with_word_counts(3, 3).unwrap();
+    assert_eq!(vec!["my red red", "my red blue", "my blue red", "my blue blue"], keyspace::as_strings(combinator.iter()));
+}
+
+#[test]
+fn test_invalid_combinators() {
+    assert!(Combinator::new(vec![]).is_err());
+    assert!(Combinator::new(test_lists(&[&["a"], &[]])).is_err());
+    let combinator = Combinator::new(test_lists(&[&["a"], &["b"]])).unwrap();
+    assert!(combinator.clone().with_separators(vec![]).is_err());
+    assert!(combinator.clone().with_word_counts(0, 2).is_err());
+    assert!(combinator.clone().with_word_counts(3, 2).is_err());
+    assert!(combinator.with_word_counts(1, 200).is_ok());
+    let wide = Combinator::new(vec![(0..1_000u32).map(|word| word.to_string().into_bytes()).collect()]).unwrap();
+    assert!(wide.with_word_counts(1, 20).is_err());
+    assert!("".parse::<Separator>().is_err());
+    assert_eq!(Ok(Separator::Capitalized), "capitalized".parse());
+}
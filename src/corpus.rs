@@ -0,0 +1,163 @@
+// Candidates taken from the owner's own texts: song lyrics, book quotes, notes. Every run of
+// min_words to max_words words of a sentence is a candidate, the longest runs of a sentence first.
+// A sentence ends at . ! ? ; and at the end of a line, so lyrics and notes split where they should and
+// a sentence wrapped over two lines is two fragments. Words are separated by whitespace, a word
+// without a letter or digit (a dash, an ellipsis) is left out.
+//
+// Each run is tried as written and with its punctuation removed, each of those as is, lowercase and
+// in sentence case: "To be, or not" "to be, or not" "To be or not" "to be or not". Files are read as
+// they are needed and every candidate is tried once, the candidates seen so far are kept in memory.
+// A line that is not UTF-8 is skipped: its words can not be told apart without knowing its encoding,
+// and decoding it lossily would only make candidates with replacement characters no one typed.
+
+use error::WarpError;
+#[cfg(test)]
+use keyspace;
+use std::collections::{HashSet, VecDeque};
+use std::path::PathBuf;
+use std::str;
+use wordlist::Wordlist;
+
+const SENTENCE_ENDS: [char; 4] = ['.', '!', '?', ';'];
+
+/// Streams the word runs of any number of text files, in order.
+pub struct Corpus {
+    lines: Wordlist,
+    min_words: usize,
+    max_words: usize,
+    pending: VecDeque<Vec<u8>>,
+    /// Everything tried so far, so a repeated chorus or quote is not tried again.
+    seen: HashSet<Vec<u8>>
+}
+
+impl Corpus {
+    /**
+     * Runs of `min_words` to `max_words` words of the lines of `lines`.
+     */
+    pub fn new(lines: Wordlist, min_words: usize, max_words: usize) -> Result<Corpus, WarpError> {
+        if min_words == 0 || min_words > max_words {
+            return Err(WarpError::InvalidPattern(format!("can not take runs of {} to {} words", min_words, max_words)));
+        }
+        Ok(Corpus { lines, min_words, max_words, pending: VecDeque::new(), seen: HashSet::new() })
+    }
+
+    /**
+     * Text files are read like wordlists: when reading reaches them, gzipped or not, "-" for stdin.
+     */
+    pub fn open<I, P>(paths: I, min_words: usize, max_words: usize) -> Result<Corpus, WarpError>
+        where I: IntoIterator<Item = P>, P: Into<PathBuf> {
+        Corpus::new(Wordlist::open(paths), min_words, max_words)
+    }
+
+    /**
+     * Queues the candidates of one line that were not tried before.
+     */
+    fn add_line(&mut self, line: &[u8]) {
+        let line = match str::from_utf8(line) {
+            Ok(line) => line,
+            Err(_) => return
+        };
+        for sentence in line.split(&SENTENCE_ENDS[..]) {
+            let words: Vec<&str> = sentence.split_whitespace()
+                .filter(|word| word.chars().any(char::is_alphanumeric))
+                .collect();
+            for length in (self.min_words..=self.max_words.min(words.len())).rev() {
+                for run in words.windows(length) {
+                    let written = run.join(" ");
+                    let stripped = run.iter()
+                        .map(|word| word.chars().filter(|c| c.is_alphanumeric()).collect::<String>())
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                    for form in &[written, stripped] {
+                        let lowercase = form.to_lowercase();
+                        for candidate in [form.clone(), sentence_case(&lowercase), lowercase] {
+                            if self.seen.insert(candidate.clone().into_bytes()) {
+                                self.pending.push_back(candidate.into_bytes());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn sentence_case(lowercase: &str) -> String {
+    let mut chars = lowercase.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+impl Iterator for Corpus {
+    type Item = Result<Vec<u8>, WarpError>;
+
+    /**
+     * Stops after the first error of the files, like a wordlist.
+     */
+    fn next(&mut self) -> Option<Result<Vec<u8>, WarpError>> {
+        loop {
+            if let Some(candidate) = self.pending.pop_front() {
+                return Some(Ok(candidate));
+            }
+            match self.lines.next()? {
+                Ok(line) => self.add_line(&line),
+                Err(error) => return Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_corpus(text: &str, min_words: usize, max_words: usize) -> Vec<String> {
+    use std::io::Cursor;
+    let lines = Wordlist::from_reader("test", Cursor::new(text.as_bytes().to_vec()));
+    Corpus::new(lines, min_words, max_words).unwrap().map(|candidate| String::from_utf8(candidate.unwrap()).unwrap()).collect()
+}
+
+#[test]
+fn test_corpus_runs() {
+    let vectors: Vec<(&str, usize, usize, Vec<&str>)> = vec![
+        // Text, fewest words, most words, candidates
+        ("to be or not", 3, 4, vec!["to be or not", "To be or not", "to be or", "To be or", "be or not", "Be or not"]),
+        ("Let It Be", 3, 3, vec!["Let It Be", "Let it be", "let it be"]),
+        ("Hey Jude, don't", 2, 2, vec![
+            "Hey Jude,", "Hey jude,", "hey jude,", "Hey Jude", "Hey jude", "hey jude", "Jude, don't", "jude, don't", "Jude dont", "jude dont"
+        ]),
+        ("One. Two words! Three more words?", 2, 2, vec!["Two words", "two words", "Three more", "three more", "more words", "More words"]),
+        ("a - b \u{2026} c", 3, 3, vec!["a b c", "A b c"]),
+        ("too short", 3, 5, vec![])
+    ];
+    for tuple in vectors {
+        println!("Testing corpus [{}]", tuple.0);
+        assert_eq!(tuple.3, test_corpus(tuple.0, tuple.1, tuple.2));
+    }
+}
+
+#[test]
+fn test_corpus_dedup() {
+    let chorus = "All you need is love\nall you need is love!\n\nLove is all you need";
+    let candidates = test_corpus(chorus, 2, 5);
+    assert_eq!(Some(&"All you need is love".to_owned()), candidates.first());
+    assert!(candidates.contains(&"Love is all you need".to_owned()));
+    assert!(!candidates.iter().any(|candidate| candidate.contains("love all")));
+    keyspace::assert_distinct(&candidates);
+
+    let candidates = test_corpus("Is this the real life\nIs this just fantasy", 4, 4);
+    assert_eq!(vec!["Is this the real", "is this the real", "this the real life", "This the real life", "Is this just fantasy", "is this just fantasy"], candidates);
+
+    assert!(Corpus::open(vec!["lyrics.txt"], 0, 3).is_err());
+    assert!(Corpus::open(vec!["lyrics.txt"], 4, 3).is_err());
+    let mut missing = Corpus::open(vec!["/nonexistent/lyrics.txt"], 2, 3).unwrap();
+    assert!(missing.next().unwrap().is_err());
+    assert!(missing.next().is_none());
+}
+
+#[test]
+fn test_corpus_skips_non_utf8_lines() {
+    use std::io::Cursor;
+    let lines = Wordlist::from_reader("test", Cursor::new(b"caf\xe9 au lait\nau lait froid".to_vec()));
+    let candidates: Vec<Vec<u8>> = Corpus::new(lines, 3, 3).unwrap().map(Result::unwrap).collect();
+    assert_eq!(vec![b"au lait froid".to_vec(), b"Au lait froid".to_vec()], candidates);
+}
@@ -0,0 +1,118 @@
+extern crate bitcoin;
+extern crate hex;
+
+use bitcoin::bitcoin::util::base58;
+use std::any::Any;
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::string::FromUtf8Error;
+use std::str::Utf8Error;
+use std::sync::mpsc::RecvError;
+
+/// Everything that can go wrong between a passphrase and an address.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WarpError {
+    /// The secret exponent is not a valid secp256k1 key (zero, >= n or not 32 bytes).
+    InvalidExponent,
+    /// The WIF could not be decoded to a private key.
+    InvalidWif(String),
+    /// A base58check or bech32 string had a bad checksum.
+    ChecksumMismatch,
+    /// The bytes are not a valid (or not the expected kind of) secp256k1 public key.
+    InvalidPublicKey,
+    /// An address could not be decoded, or is not valid for what it is used for.
+    InvalidAddress(String),
+    /// Input bytes were expected to be UTF-8 but were not.
+    NonUtf8Input,
+    /// Scrypt or pbkdf2 parameters that would make the KDF panic or run forever.
+    InvalidKdfParameters(String),
+    /// A worker thread panicked or hung up before returning a result.
+    WorkerPanicked,
+    /// The operating system random number generator could not be opened.
+    RandomnessUnavailable(String),
+    /// Reading or writing a file failed.
+    Io(String),
+    /// A file is not a hash160 index, or is truncated.
+    InvalidIndex(String),
+    /// A mask or other candidate pattern could not be parsed.
+    InvalidPattern(String)
+}
+
+impl fmt::Display for WarpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            WarpError::InvalidExponent => write!(f, "secret exponent is not a valid secp256k1 key"),
+            WarpError::InvalidWif(ref reason) => write!(f, "invalid private key wif: {}", reason),
+            WarpError::ChecksumMismatch => write!(f, "checksum mismatch"),
+            WarpError::InvalidPublicKey => write!(f, "invalid public key"),
+            WarpError::InvalidAddress(ref reason) => write!(f, "invalid address: {}", reason),
+            WarpError::NonUtf8Input => write!(f, "input is not valid UTF-8"),
+            WarpError::InvalidKdfParameters(ref reason) => write!(f, "invalid KDF parameters: {}", reason),
+            WarpError::WorkerPanicked => write!(f, "worker thread panicked"),
+            WarpError::RandomnessUnavailable(ref reason) => write!(f, "no secure random numbers available: {}", reason),
+            WarpError::Io(ref reason) => write!(f, "i/o error: {}", reason),
+            WarpError::InvalidIndex(ref reason) => write!(f, "invalid index: {}", reason),
+            WarpError::InvalidPattern(ref reason) => write!(f, "invalid pattern: {}", reason)
+        }
+    }
+}
+
+impl Error for WarpError {}
+
+impl From<base58::Error> for WarpError {
+    fn from(error: base58::Error) -> WarpError {
+        match error {
+            base58::Error::BadChecksum(_, _) => WarpError::ChecksumMismatch,
+            other => WarpError::InvalidWif(format!("{:?}", other))
+        }
+    }
+}
+
+impl From<hex::FromHexError> for WarpError {
+    fn from(_: hex::FromHexError) -> WarpError {
+        WarpError::InvalidExponent
+    }
+}
+
+impl From<FromUtf8Error> for WarpError {
+    fn from(_: FromUtf8Error) -> WarpError {
+        WarpError::NonUtf8Input
+    }
+}
+
+impl From<Utf8Error> for WarpError {
+    fn from(_: Utf8Error) -> WarpError {
+        WarpError::NonUtf8Input
+    }
+}
+
+impl From<io::Error> for WarpError {
+    fn from(error: io::Error) -> WarpError {
+        WarpError::Io(error.to_string())
+    }
+}
+
+impl From<RecvError> for WarpError {
+    fn from(_: RecvError) -> WarpError {
+        WarpError::WorkerPanicked
+    }
+}
+
+// What thread::spawn(..).join() hands back when the thread panicked.
+impl From<Box<dyn Any + Send + 'static>> for WarpError {
+    fn from(_: Box<dyn Any + Send + 'static>) -> WarpError {
+        WarpError::WorkerPanicked
+    }
+}
+
+#[test]
+fn test_thread_panic_becomes_worker_panicked() {
+    use std::thread;
+
+    fn run() -> Result<u8, WarpError> {
+        let value = thread::spawn(move || -> u8 { panic!("worker blew up") }).join()?;
+        Ok(value)
+    }
+    assert_eq!(Err(WarpError::WorkerPanicked), run());
+}
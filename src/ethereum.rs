@@ -0,0 +1,269 @@
+// Ethereum keys from the same secp256k1 exponent: the EIP-55 address and a Web3 Secret Storage
+// (version 3) keystore, the JSON wallet file geth, MetaMask and MyEtherWallet import.
+//
+//    address    =   keccak256(uncompressed public key without its 0x04 prefix)[12..32]
+//    derived    =   kdf(password, salt)
+//    ciphertext =   aes-128-ctr(key=derived[0..16], iv, secret exponent)
+//    mac        =   keccak256(derived[16..32] || ciphertext)
+
+extern crate crypto;
+extern crate hex;
+
+use bitcoin::SecretExponent;
+use error::WarpError;
+use pbkdf2::perform_pbkdf2;
+use rand::{OsRng, Rng};
+use scrypt::perform_scrypt;
+use self::crypto::aes::{self, KeySize};
+use self::crypto::symmetriccipher::SynchronousStreamCipher;
+use sha256::keccak256;
+use std::fmt;
+use std::str::FromStr;
+
+const KEYSTORE_KEY_LENGTH: usize = 32;
+const KEYSTORE_SALT_LENGTH: usize = 32;
+const KEYSTORE_IV_LENGTH: usize = 16;
+
+/// An EIP-55 mixed case checksummed address, 0x prefixed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthereumAddress(String);
+
+impl EthereumAddress {
+    pub fn from_exponent(exponent: &SecretExponent) -> Result<EthereumAddress, WarpError> {
+        let public_key = exponent.to_public_key(false)?;
+        public_key_to_ethereum_address(public_key.as_bytes()).map(EthereumAddress)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for EthereumAddress {
+    type Err = WarpError;
+
+    /**
+     * All lowercase and all uppercase addresses carry no checksum and are accepted as is,
+     * mixed case ones have to match their EIP-55 checksum.
+     */
+    fn from_str(address: &str) -> Result<EthereumAddress, WarpError> {
+        let digits = address.strip_prefix("0x").unwrap_or(address);
+        if digits.len() != 40 || hex::decode(digits).is_err() {
+            return Err(WarpError::InvalidAddress("ethereum addresses are 20 hex encoded bytes".to_owned()));
+        }
+        let checksummed = eip55_checksum(&digits.to_ascii_lowercase());
+        let has_lower = digits.bytes().any(|byte| byte.is_ascii_lowercase());
+        let has_upper = digits.bytes().any(|byte| byte.is_ascii_uppercase());
+        if has_lower && has_upper && &checksummed[2..] != digits {
+            return Err(WarpError::ChecksumMismatch);
+        }
+        Ok(EthereumAddress(checksummed))
+    }
+}
+
+impl fmt::Display for EthereumAddress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/**
+ * Capitalizes every letter whose nibble in keccak256(lowercase hex address) is 8 or more.
+ */
+fn eip55_checksum(lowercase_hex: &str) -> String {
+    let hash = keccak256(lowercase_hex.as_bytes());
+    let mut checksummed = String::with_capacity(lowercase_hex.len() + 2);
+    checksummed.push_str("0x");
+    for (index, character) in lowercase_hex.chars().enumerate() {
+        let nibble = (hash[index / 2] >> (if index % 2 == 0 { 4 } else { 0 })) & 0x0f;
+        if nibble >= 8 {
+            checksummed.push(character.to_ascii_uppercase());
+        } else {
+            checksummed.push(character);
+        }
+    }
+    checksummed
+}
+
+/**
+ * EIP-55 address of a 65 byte uncompressed public key.
+ */
+pub fn public_key_to_ethereum_address(public_key: &[u8]) -> Result<String, WarpError> {
+    if public_key.len() != 65 || public_key[0] != 0x04 {
+        return Err(WarpError::InvalidPublicKey);
+    }
+    let hash = keccak256(&public_key[1..]);
+    Ok(eip55_checksum(&hex::encode(&hash[12..])))
+}
+
+/// The key derivation function of a keystore and its parameters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeystoreKdf {
+    Scrypt { n: u32, r: u32, p: u32 },
+    Pbkdf2 { c: u32 }
+}
+
+impl KeystoreKdf {
+    /**
+     * The scrypt parameters geth uses for its "standard" keystores.
+     */
+    pub fn standard() -> KeystoreKdf {
+        KeystoreKdf::Scrypt { n: 262_144, r: 8, p: 1 }
+    }
+
+    fn derive(&self, password: &[u8], salt: &[u8]) -> Result<Vec<u8>, WarpError> {
+        match *self {
+            KeystoreKdf::Scrypt { n, r, p } => perform_scrypt(password.to_vec(), salt.to_vec(), n, r, p, KEYSTORE_KEY_LENGTH),
+            KeystoreKdf::Pbkdf2 { c } => perform_pbkdf2(password.to_vec(), salt.to_vec(), c, KEYSTORE_KEY_LENGTH)
+        }
+    }
+
+    fn to_json(self, salt: &[u8]) -> String {
+        match self {
+            KeystoreKdf::Scrypt { n, r, p } => format!(
+                r#""kdf":"scrypt","kdfparams":{{"dklen":{},"n":{},"p":{},"r":{},"salt":"{}"}}"#,
+                KEYSTORE_KEY_LENGTH, n, p, r, hex::encode(salt)
+            ),
+            KeystoreKdf::Pbkdf2 { c } => format!(
+                r#""kdf":"pbkdf2","kdfparams":{{"c":{},"dklen":{},"prf":"hmac-sha256","salt":"{}"}}"#,
+                c, KEYSTORE_KEY_LENGTH, hex::encode(salt)
+            )
+        }
+    }
+}
+
+impl Default for KeystoreKdf {
+    fn default() -> KeystoreKdf {
+        KeystoreKdf::standard()
+    }
+}
+
+/**
+ * Encrypts the exponent into a version 3 keystore JSON with a fresh random salt, iv and id.
+ */
+pub fn keystore_v3(exponent: &SecretExponent, password: &str, kdf: KeystoreKdf) -> Result<String, WarpError> {
+    let mut rng = OsRng::new().map_err(|error| WarpError::RandomnessUnavailable(error.to_string()))?;
+    let mut salt = [0u8; KEYSTORE_SALT_LENGTH];
+    let mut iv = [0u8; KEYSTORE_IV_LENGTH];
+    let mut id = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut iv);
+    rng.fill_bytes(&mut id);
+    encrypt_keystore(exponent, password, kdf, &salt, &iv, &uuid_v4(id))
+}
+
+/**
+ * Formats 16 random bytes as a version 4 (random) UUID.
+ */
+fn uuid_v4(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex_bytes = hex::encode(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex_bytes[0..8], &hex_bytes[8..12], &hex_bytes[12..16], &hex_bytes[16..20], &hex_bytes[20..32]
+    )
+}
+
+fn encrypt_keystore(exponent: &SecretExponent, password: &str, kdf: KeystoreKdf, salt: &[u8], iv: &[u8], id: &str) -> Result<String, WarpError> {
+    let derived = kdf.derive(password.as_bytes(), salt)?;
+
+    let mut ciphertext = vec![0u8; exponent.as_bytes().len()];
+    aes::ctr(KeySize::KeySize128, &derived[0..16], iv).process(exponent.as_bytes(), &mut ciphertext);
+
+    let mut mac_input = derived[16..32].to_vec();
+    mac_input.extend_from_slice(&ciphertext);
+    let mac = keccak256(&mac_input);
+
+    let address = EthereumAddress::from_exponent(exponent)?;
+    Ok(format!(
+        r#"{{"address":"{}","crypto":{{"cipher":"aes-128-ctr","cipherparams":{{"iv":"{}"}},"ciphertext":"{}",{},"mac":"{}"}},"id":"{}","version":3}}"#,
+        address.as_str()[2..].to_ascii_lowercase(),
+        hex::encode(iv),
+        hex::encode(&ciphertext),
+        kdf.to_json(salt),
+        hex::encode(&mac),
+        id
+    ))
+}
+
+#[test]
+fn test_ethereum_addresses() {
+    let vectors = vec![
+        // EIP-55 address, secret exponent
+        ("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf", "0000000000000000000000000000000000000000000000000000000000000001"),
+        ("0x09332B1E45e6172fB26E46B3DB4411201547560a", "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"),
+        ("0x008AeEda4D805471dF9b2A5B0f38A0C3bCBA786b", "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d")
+    ];
+    for tuple in vectors {
+        println!("Testing exponent [{}]", tuple.1);
+        let exponent: SecretExponent = tuple.1.parse().unwrap();
+        assert_eq!(tuple.0, EthereumAddress::from_exponent(&exponent).unwrap().to_string());
+        assert_eq!(tuple.0, tuple.0.parse::<EthereumAddress>().unwrap().to_string());
+        assert_eq!(tuple.0, tuple.0.to_ascii_lowercase().parse::<EthereumAddress>().unwrap().to_string());
+    }
+    let compressed = "0000000000000000000000000000000000000000000000000000000000000001".parse::<SecretExponent>().unwrap()
+        .to_public_key(true).unwrap();
+    assert_eq!(Err(WarpError::InvalidPublicKey), public_key_to_ethereum_address(compressed.as_bytes()));
+}
+
+#[test]
+fn test_eip55_checksums() {
+    let vectors = vec![
+        // The examples of EIP-55
+        "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+        "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+        "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb"
+    ];
+    for vector in vectors {
+        println!("Testing checksum of [{}]", vector);
+        assert_eq!(vector, eip55_checksum(&vector[2..].to_ascii_lowercase()));
+        assert_eq!(vector, vector.parse::<EthereumAddress>().unwrap().to_string());
+    }
+    assert_eq!(Err(WarpError::ChecksumMismatch), "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD".parse::<EthereumAddress>());
+    assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeA".parse::<EthereumAddress>().is_err());
+    assert!("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeg".parse::<EthereumAddress>().is_err());
+}
+
+#[test]
+fn test_keystore_v3_vectors() {
+    let exponent: SecretExponent = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d".parse().unwrap();
+    let id = "3198bc9c-6672-5ab3-d995-4942343ae5b6";
+
+    // The PBKDF2-SHA-256 test vector of the Web3 Secret Storage definition.
+    let pbkdf2_salt = hex::decode("ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd").unwrap();
+    let pbkdf2_iv = hex::decode("6087dab2f9fdbbfaddc31a909735c1e6").unwrap();
+    let keystore = encrypt_keystore(&exponent, "testpassword", KeystoreKdf::Pbkdf2 { c: 262_144 }, &pbkdf2_salt, &pbkdf2_iv, id).unwrap();
+    assert_eq!(concat!(
+        r#"{"address":"008aeeda4d805471df9b2a5b0f38a0c3bcba786b","crypto":{"cipher":"aes-128-ctr","#,
+        r#""cipherparams":{"iv":"6087dab2f9fdbbfaddc31a909735c1e6"},"#,
+        r#""ciphertext":"5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46","kdf":"pbkdf2","#,
+        r#""kdfparams":{"c":262144,"dklen":32,"prf":"hmac-sha256","salt":"ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"},"#,
+        r#""mac":"517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"},"#,
+        r#""id":"3198bc9c-6672-5ab3-d995-4942343ae5b6","version":3}"#
+    ), keystore);
+
+    // Scrypt with a reduced N, the vector of the definition uses r = 1 which rust-crypto rejects for N = 2^18.
+    let scrypt_salt = hex::decode("ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19").unwrap();
+    let scrypt_iv = hex::decode("83dbcc02d8ccb40e466191a123791e0e").unwrap();
+    let keystore = encrypt_keystore(&exponent, "warp", KeystoreKdf::Scrypt { n: 1_024, r: 8, p: 1 }, &scrypt_salt, &scrypt_iv, id).unwrap();
+    assert!(keystore.contains(r#""ciphertext":"6126ff53184ce5b6f117c9565e28095d7c450d77211be24b6549f56a0f6e8462""#));
+    assert!(keystore.contains(r#""kdf":"scrypt","kdfparams":{"dklen":32,"n":1024,"p":1,"r":8,"salt":"ab0c7876052600dd703518d6fc3fe8984592145b591fc8fb5c6d43190334ba19"}"#));
+    assert!(keystore.contains(r#""mac":"c0d760b1d4b9d796914ff50018cf7d56eb7ca184984e677b5fb32c12d3630bdd""#));
+
+    assert!(encrypt_keystore(&exponent, "warp", KeystoreKdf::Scrypt { n: 1_000, r: 8, p: 1 }, &scrypt_salt, &scrypt_iv, id).is_err());
+}
+
+#[test]
+fn test_random_keystores_differ() {
+    let exponent: SecretExponent = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".parse().unwrap();
+    let kdf = KeystoreKdf::Scrypt { n: 1_024, r: 8, p: 1 };
+    let first = keystore_v3(&exponent, "password", kdf).unwrap();
+    let second = keystore_v3(&exponent, "password", kdf).unwrap();
+    assert!(first.starts_with(r#"{"address":"09332b1e45e6172fb26e46b3db4411201547560a","#));
+    assert!(first != second);
+
+    assert_eq!("00000000-0000-4000-8000-000000000000", uuid_v4([0u8; 16]));
+    assert_eq!("ffffffff-ffff-4fff-bfff-ffffffffffff", uuid_v4([0xffu8; 16]));
+}
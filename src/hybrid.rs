@@ -0,0 +1,150 @@
+// Hybrid candidates, hashcat's -a 6 and -a 7: every candidate of a mask appended to, or prepended to,
+// every word of a wordlist, "known phrase" + "?d?d?d?d" or "?d?d?d?d" + "word". The words are
+// loaded so the keyspace is exact, words times the mask keyspace, and candidate n is word n / mask
+// keyspace with mask candidate n % mask keyspace.
+
+use error::WarpError;
+use keyspace::{self, Keyspace};
+use mask::Mask;
+use std::path::Path;
+use std::str::FromStr;
+use wordlist::Wordlist;
+
+/// Which end of the word the mask goes on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HybridSide {
+    Append,
+    Prepend
+}
+
+impl FromStr for HybridSide {
+    type Err = WarpError;
+
+    fn from_str(side: &str) -> Result<HybridSide, WarpError> {
+        match side {
+            "append" => Ok(HybridSide::Append),
+            "prepend" => Ok(HybridSide::Prepend),
+            _ => Err(WarpError::InvalidPattern(format!("{} is neither append nor prepend", side)))
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hybrid {
+    words: Vec<Vec<u8>>,
+    mask: Mask,
+    side: HybridSide
+}
+
+impl Hybrid {
+    pub fn new(words: Vec<Vec<u8>>, mask: Mask, side: HybridSide) -> Result<Hybrid, WarpError> {
+        if words.is_empty() {
+            return Err(WarpError::InvalidPattern("a hybrid search needs at least one word".to_owned()));
+        }
+        mask.keyspace().checked_mul(words.len() as u128)
+            .ok_or_else(keyspace::overflow)?;
+        Ok(Hybrid { words, mask, side })
+    }
+
+    /**
+     * Reads every wordlist into memory, in order. A word listed twice is tried twice, as hashcat does.
+     */
+    pub fn load<P: AsRef<Path>>(paths: &[P], mask: Mask, side: HybridSide) -> Result<Hybrid, WarpError> {
+        let words = Wordlist::open(paths.iter().map(|path| path.as_ref().to_path_buf()))
+            .collect::<Result<Vec<Vec<u8>>, WarpError>>()?;
+        Hybrid::new(words, mask, side)
+    }
+
+    fn join(&self, word: &[u8], expansion: &[u8]) -> Vec<u8> {
+        match self.side {
+            HybridSide::Append => [word, expansion].concat(),
+            HybridSide::Prepend => [expansion, word].concat()
+        }
+    }
+}
+
+impl Keyspace for Hybrid {
+    /**
+     * Words times the mask keyspace.
+     */
+    fn keyspace(&self) -> u128 {
+        self.words.len() as u128 * self.mask.keyspace()
+    }
+
+    fn candidate(&self, index: u128) -> Option<Vec<u8>> {
+        if index >= self.keyspace() {
+            return None;
+        }
+        let expansions = self.mask.keyspace();
+        let word = &self.words[(index / expansions) as usize];
+        self.mask.candidate(index % expansions).map(|expansion| self.join(word, &expansion))
+    }
+
+    fn iter_from(&self, start: u128) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        let expansions = self.mask.keyspace();
+        let word = (start / expansions).min(self.words.len() as u128) as usize;
+        Box::new(HybridIter { hybrid: self, word, expansions: self.mask.iter_from(start % expansions) })
+    }
+}
+
+/// Walks a hybrid keyspace, the mask turning for every word.
+struct HybridIter<'a> {
+    hybrid: &'a Hybrid,
+    word: usize,
+    expansions: Box<dyn Iterator<Item = Vec<u8>> + 'a>
+}
+
+impl<'a> Iterator for HybridIter<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let word = self.hybrid.words.get(self.word)?;
+            match self.expansions.next() {
+                Some(expansion) => return Some(self.hybrid.join(word, &expansion)),
+                None => {
+                    self.word += 1;
+                    self.expansions = self.hybrid.mask.iter();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn test_words(words: &[&str]) -> Vec<Vec<u8>> {
+    words.iter().map(|word| word.as_bytes().to_vec()).collect()
+}
+
+#[test]
+fn test_hybrid_append_prepend() {
+    let vectors: Vec<(HybridSide, Vec<&str>)> = vec![
+        // Side, candidates
+        (HybridSide::Append, vec!["cow0", "cow1", "cow2", "pig0", "pig1", "pig2"]),
+        (HybridSide::Prepend, vec!["0cow", "1cow", "2cow", "0pig", "1pig", "2pig"])
+    ];
+    for tuple in vectors {
+        println!("Testing hybrid {:?}", tuple.0);
+        let mask = Mask::parse("?1", &["012"]).unwrap();
+        let hybrid = Hybrid::new(test_words(&["cow", "pig"]), mask, tuple.0).unwrap();
+        assert_eq!(6, hybrid.keyspace());
+        assert_eq!(tuple.1, keyspace::as_strings(keyspace::assert_numbering(&hybrid)));
+        assert_eq!(None, hybrid.candidate(3 << 64));
+        assert_eq!(0, hybrid.iter_from(1_000).count());
+    }
+}
+
+#[test]
+fn test_hybrid_increment() {
+    let mask = Mask::parse("?d?d?d?d", &[] as &[&str]).unwrap().with_lengths(2, 4).unwrap();
+    let hybrid = Hybrid::new(test_words(&["Dutch Power Cows ", "warp"]), mask, HybridSide::Append).unwrap();
+    assert_eq!(2 * (100 + 1_000 + 10_000), hybrid.keyspace());
+    assert_eq!(Some(b"Dutch Power Cows 00".to_vec()), hybrid.candidate(0));
+    assert_eq!(Some(b"Dutch Power Cows 9999".to_vec()), hybrid.candidate(11_099));
+    assert_eq!(Some(b"warp00".to_vec()), hybrid.candidate(11_100));
+    assert_eq!(vec![b"warp9998".to_vec(), b"warp9999".to_vec()], hybrid.iter_from(22_198).collect::<Vec<Vec<u8>>>());
+    assert_eq!(hybrid.keyspace(), hybrid.iter().count() as u128);
+
+    assert!(Hybrid::new(vec![], Mask::parse("?d", &[] as &[&str]).unwrap(), HybridSide::Append).is_err());
+    assert!("middle".parse::<HybridSide>().is_err());
+}
@@ -0,0 +1,389 @@
+// A sorted table of target hashes on disk, memory-mapped so millions of targets cost no heap and
+// a lookup is a binary search. An optional Bloom filter in front answers most misses from memory.
+// Opening a file reads it once to check the records are sorted, a binary search would silently
+// miss targets of a hand edited or foreign file.
+//
+// File layout: "WARPH160", the record count as a little endian u64, then the records sorted by hash.
+// A record is the 20 byte hash, the TargetKind and a tag to rebuild the address from: the version
+// byte of base58 addresses, the hrp position in segwit_hrps() for P2WPKH and 0 for Ethereum.
+
+extern crate bitcoin;
+extern crate hex;
+extern crate memmap;
+
+use self::bitcoin::util::base58::{FromBase58, ToBase58};
+use self::memmap::Mmap;
+use bech32;
+use coins::CoinProfile;
+use error::WarpError;
+use ethereum::EthereumAddress;
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use targets::{Target, TargetKind};
+
+const MAGIC: &[u8; 8] = b"WARPH160";
+const HEADER_LENGTH: usize = 16;
+const HASH_LENGTH: usize = 20;
+pub const RECORD_LENGTH: usize = HASH_LENGTH + 2;
+
+/// What building an index did with its input lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub duplicates: usize,
+    /// Blank, comment, invalid and taproot lines. Taproot output keys are 32 bytes and do not fit.
+    pub skipped: usize
+}
+
+/// A fixed size Bloom filter over 20 byte hashes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hash_count: u32
+}
+
+impl BloomFilter {
+    /**
+     * Sized for `entries` hashes at `bits_per_entry` bits each, 10 bits give about 1% false positives.
+     */
+    pub fn new(entries: usize, bits_per_entry: usize) -> BloomFilter {
+        let bit_count = (entries * bits_per_entry).max(64);
+        let hash_count = ((bits_per_entry as f64) * ::std::f64::consts::LN_2).round().max(1.0) as u32;
+        BloomFilter { bits: vec![0; bit_count.div_ceil(64)], hash_count }
+    }
+
+    // The input is already a cryptographic hash, so two of its words are independent enough
+    // for double hashing.
+    fn positions(&self, hash: &[u8]) -> Vec<usize> {
+        let mut first = [0u8; 8];
+        let mut second = [0u8; 8];
+        first.copy_from_slice(&hash[0..8]);
+        second.copy_from_slice(&hash[8..16]);
+        let (first, second) = (u64::from_le_bytes(first), u64::from_le_bytes(second) | 1);
+        let bit_count = (self.bits.len() * 64) as u64;
+        (0..u64::from(self.hash_count))
+            .map(|index| (first.wrapping_add(index.wrapping_mul(second)) % bit_count) as usize)
+            .collect()
+    }
+
+    pub fn insert(&mut self, hash: &[u8]) {
+        for position in self.positions(hash) {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    /**
+     * False means the hash was never inserted, true means it probably was.
+     */
+    pub fn contains(&self, hash: &[u8]) -> bool {
+        self.positions(hash).iter().all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+}
+
+/// A memory-mapped index file.
+pub struct HashIndex {
+    map: Mmap,
+    count: usize,
+    bloom: Option<BloomFilter>
+}
+
+impl HashIndex {
+    /**
+     * Writes an index of the addresses in `addresses`, one per line, to `path`. Lines that are not
+     * an indexable address are counted and skipped rather than failing a long export.
+     */
+    pub fn build<R: BufRead, P: AsRef<Path>>(addresses: R, path: P) -> Result<IndexSummary, WarpError> {
+        let mut summary = IndexSummary::default();
+        let mut records: Vec<[u8; RECORD_LENGTH]> = vec![];
+        for line in addresses.lines() {
+            let line = line?;
+            match line.trim().parse::<Target>().ok().and_then(|target| to_record(&target)) {
+                Some(record) => records.push(record),
+                None => summary.skipped += 1
+            }
+        }
+        records.sort_unstable();
+        let before = records.len();
+        records.dedup();
+        summary.duplicates = before - records.len();
+        summary.indexed = records.len();
+
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&(records.len() as u64).to_le_bytes())?;
+        for record in &records {
+            file.write_all(record)?;
+        }
+        file.flush()?;
+        Ok(summary)
+    }
+
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<HashIndex, WarpError> {
+        let file = File::open(path)?;
+        // The file is only read, changing it while a search runs is not supported.
+        let map = unsafe { Mmap::map(&file)? };
+        if map.len() < HEADER_LENGTH || map[0..8] != MAGIC[..] {
+            return Err(WarpError::InvalidIndex("not a hash160 index file".to_owned()));
+        }
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&map[8..16]);
+        let count = u64::from_le_bytes(count);
+        let length = count.checked_mul(RECORD_LENGTH as u64).and_then(|records| records.checked_add(HEADER_LENGTH as u64));
+        if length != Some(map.len() as u64) {
+            return Err(WarpError::InvalidIndex(format!("expected {} records, the file is {} bytes", count, map.len())));
+        }
+        // The length matches the file, so the count fits in a usize.
+        let index = HashIndex { map, count: count as usize, bloom: None };
+        for position in 1..index.count {
+            if index.record(position - 1)[..HASH_LENGTH] > index.record(position)[..HASH_LENGTH] {
+                return Err(WarpError::InvalidIndex(format!("record {} is out of order", position)));
+            }
+        }
+        Ok(index)
+    }
+
+    /**
+     * Puts a Bloom filter in front of the table. Building it reads the whole file once.
+     */
+    pub fn with_bloom_filter(mut self, bits_per_entry: usize) -> HashIndex {
+        let mut bloom = BloomFilter::new(self.count, bits_per_entry);
+        for index in 0..self.count {
+            bloom.insert(&self.record(index)[..HASH_LENGTH]);
+        }
+        self.bloom = Some(bloom);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    fn record(&self, index: usize) -> &[u8] {
+        let start = HEADER_LENGTH + index * RECORD_LENGTH;
+        &self.map[start..start + RECORD_LENGTH]
+    }
+
+    /**
+     * The targets with this hash, for every kind and coin they were indexed with.
+     */
+    pub fn lookup(&self, hash: &[u8]) -> Vec<Target> {
+        if hash.len() != HASH_LENGTH {
+            return vec![];
+        }
+        if let Some(ref bloom) = self.bloom {
+            if !bloom.contains(hash) {
+                return vec![];
+            }
+        }
+        // Lower bound, then every record with the same hash.
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            match self.record(middle)[..HASH_LENGTH].cmp(hash) {
+                Ordering::Less => low = middle + 1,
+                _ => high = middle
+            }
+        }
+        (low..self.count)
+            .map(|index| self.record(index))
+            .take_while(|record| record[..HASH_LENGTH] == *hash)
+            .filter_map(from_record)
+            .collect()
+    }
+}
+
+impl fmt::Debug for HashIndex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HashIndex {{ count: {}, bloom: {} }}", self.count, self.bloom.is_some())
+    }
+}
+
+/**
+ * The distinct segwit hrps of the known coins, in a fixed order.
+ */
+fn segwit_hrps() -> Vec<&'static str> {
+    let mut hrps: Vec<&'static str> = vec![];
+    for hrp in CoinProfile::all().into_iter().filter_map(|coin| coin.bech32_hrp) {
+        if !hrps.contains(&hrp) {
+            hrps.push(hrp);
+        }
+    }
+    hrps
+}
+
+fn kind_byte(kind: TargetKind) -> u8 {
+    match kind {
+        TargetKind::PubkeyHash => 0,
+        TargetKind::ScriptHash => 1,
+        TargetKind::WitnessPubkeyHash => 2,
+        TargetKind::Taproot => 3,
+        TargetKind::Ethereum => 4
+    }
+}
+
+fn to_record(target: &Target) -> Option<[u8; RECORD_LENGTH]> {
+    let tag = match target.kind {
+        TargetKind::PubkeyHash | TargetKind::ScriptHash => decode_version(&target.address)?,
+        TargetKind::WitnessPubkeyHash => {
+            let lowered = target.address.to_ascii_lowercase();
+            segwit_hrps().iter().position(|hrp| lowered.starts_with(&format!("{}1", hrp)))? as u8
+        },
+        TargetKind::Ethereum => 0,
+        TargetKind::Taproot => return None
+    };
+    let mut record = [0u8; RECORD_LENGTH];
+    record[..HASH_LENGTH].copy_from_slice(&target.hash);
+    record[HASH_LENGTH] = kind_byte(target.kind);
+    record[HASH_LENGTH + 1] = tag;
+    Some(record)
+}
+
+fn decode_version(address: &str) -> Option<u8> {
+    let payload: Vec<u8> = FromBase58::from_base58check(address).ok()?;
+    payload.first().cloned()
+}
+
+fn from_record(record: &[u8]) -> Option<Target> {
+    let hash = record[..HASH_LENGTH].to_vec();
+    let tag = record[HASH_LENGTH + 1];
+    let (kind, address) = match record[HASH_LENGTH] {
+        0 | 1 => {
+            let mut payload = vec![tag];
+            payload.extend_from_slice(&hash);
+            let kind = if record[HASH_LENGTH] == 0 { TargetKind::PubkeyHash } else { TargetKind::ScriptHash };
+            (kind, payload.to_base58check())
+        },
+        2 => {
+            let hrp = *segwit_hrps().get(tag as usize)?;
+            (TargetKind::WitnessPubkeyHash, bech32::encode_segwit_address(hrp, 0, &hash).ok()?)
+        },
+        4 => {
+            let address: EthereumAddress = format!("0x{}", hex::encode(&hash)).parse().ok()?;
+            (TargetKind::Ethereum, address.to_string())
+        },
+        _ => return None
+    };
+    Some(Target { address, kind, hash })
+}
+
+#[cfg(test)]
+fn write_test_file(name: &str, lines: &[&str]) -> ::std::path::PathBuf {
+    let path = ::std::env::temp_dir().join(name);
+    let mut file = File::create(&path).unwrap();
+    for line in lines {
+        writeln!(file, "{}", line).unwrap();
+    }
+    path
+}
+
+#[test]
+fn test_build_and_lookup_index() {
+    use std::fs;
+    use std::io::BufReader;
+
+    let addresses = vec![
+        "# exported funded addresses",
+        "1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMb",
+        "12q7HJP6LFwMHFWCogVzjq7BsHt8tqWfur",
+        "D6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs",
+        "3KKCYKKJjFtxQa4nPSnk2g8EXPo62r3Pzr",
+        "bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz",
+        "bc1pwwtzg63krwfvqkv5pca95kg50hkugxjwucaccp9xsl2ftltscmgqrg8gu9",
+        "0x09332B1E45e6172fB26E46B3DB4411201547560a",
+        "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA",
+        "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA",
+        "not an address"
+    ];
+    let input = write_test_file("rust-warpwallet-test-index.txt", &addresses);
+    let output = ::std::env::temp_dir().join("rust-warpwallet-test-index.h160");
+    let summary = HashIndex::build(BufReader::new(File::open(&input).unwrap()), &output).unwrap();
+    assert_eq!(IndexSummary { indexed: 7, duplicates: 1, skipped: 3 }, summary);
+
+    for bits_per_entry in vec![None, Some(10)] {
+        let mut index = HashIndex::open(&output).unwrap();
+        if let Some(bits) = bits_per_entry {
+            index = index.with_bloom_filter(bits);
+        }
+        assert_eq!(7, index.len());
+        for address in &addresses[1..9] {
+            let target: Target = address.parse().unwrap();
+            println!("Testing lookup of [{}]", address);
+            let found = index.lookup(&target.hash);
+            if target.kind == TargetKind::Taproot {
+                assert!(found.is_empty());
+            } else {
+                assert!(found.contains(&target));
+            }
+        }
+        // The bitcoin and dogecoin P2PKH addresses of the same key share a hash.
+        let dogecoin: Target = "D6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs".parse().unwrap();
+        assert_eq!(2, index.lookup(&dogecoin.hash).iter().filter(|target| target.kind == TargetKind::PubkeyHash).count());
+        assert!(index.lookup(&[0u8; 20]).is_empty());
+        assert!(index.lookup(&[0xffu8; 20]).is_empty());
+    }
+
+    fs::remove_file(&input).unwrap();
+    fs::remove_file(&output).unwrap();
+}
+
+#[test]
+fn test_open_invalid_index() {
+    use std::fs;
+
+    let path = write_test_file("rust-warpwallet-test-not-an-index.h160", &["1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]);
+    match HashIndex::open(&path) {
+        Err(WarpError::InvalidIndex(_)) => (),
+        other => panic!("expected an invalid index error, got {:?}", other)
+    }
+
+    let record = |first: u8| {
+        let mut record = [first; RECORD_LENGTH];
+        record[HASH_LENGTH] = 0;
+        record
+    };
+    let vectors: Vec<(&str, u64, Vec<[u8; RECORD_LENGTH]>)> = vec![
+        // Case, header record count, records
+        ("count overflows", u64::MAX, vec![record(1)]),
+        ("count wraps to the file length", (1 << 63) + 1, vec![record(1)]),
+        ("too few records", 2, vec![record(1)]),
+        ("out of order", 2, vec![record(2), record(1)])
+    ];
+    for tuple in vectors {
+        println!("Testing invalid index, {}", tuple.0);
+        let mut file = File::create(&path).unwrap();
+        file.write_all(MAGIC).unwrap();
+        file.write_all(&tuple.1.to_le_bytes()).unwrap();
+        for record in &tuple.2 {
+            file.write_all(record).unwrap();
+        }
+        drop(file);
+        match HashIndex::open(&path) {
+            Err(WarpError::InvalidIndex(_)) => (),
+            other => panic!("expected an invalid index error, got {:?}", other)
+        }
+    }
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_bloom_filter() {
+    let entries = 10_000;
+    let mut bloom = BloomFilter::new(entries, 10);
+    let hash_of = |index: u32| ::sha256::hash160(&index.to_le_bytes());
+    for index in 0..entries as u32 {
+        bloom.insert(&hash_of(index));
+    }
+    for index in 0..entries as u32 {
+        assert!(bloom.contains(&hash_of(index)));
+    }
+    let false_positives = (entries as u32..2 * entries as u32).filter(|index| bloom.contains(&hash_of(*index))).count();
+    println!("{} false positives in {} misses", false_positives, entries);
+    assert!(false_positives < entries / 50);
+}
@@ -0,0 +1,68 @@
+// Candidate sources with an exact keyspace: every candidate has a number, so --keyspace can count
+//      them up front and --skip can resume a search from any index. Candidate n is always the same
+//      phrase, and iterating from n yields candidates n, n + 1, ... in the same order.
+
+use error::WarpError;
+
+pub trait Keyspace {
+    /**
+     * The exact number of candidates.
+     */
+    fn keyspace(&self) -> u128;
+
+    /**
+     * Candidate number `index`, None past the end of the keyspace.
+     */
+    fn candidate(&self, index: u128) -> Option<Vec<u8>>;
+
+    /**
+     * Every candidate from `start` on, in keyspace order.
+     */
+    fn iter_from(&self, start: u128) -> Box<dyn Iterator<Item = Vec<u8>> + '_>;
+
+    fn iter(&self) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        self.iter_from(0)
+    }
+}
+
+/**
+ * The error of a source whose candidates can not all be numbered.
+ */
+pub fn overflow() -> WarpError {
+    WarpError::InvalidPattern("the keyspace does not fit in 128 bits".to_owned())
+}
+
+/**
+ * Checks that iter, candidate and iter_from agree and all stop at the keyspace, and returns
+ * the candidates in order.
+ */
+#[cfg(test)]
+pub fn assert_numbering<K: Keyspace>(source: &K) -> Vec<Vec<u8>> {
+    let candidates: Vec<Vec<u8>> = source.iter().collect();
+    assert_eq!(source.keyspace(), candidates.len() as u128);
+    for (index, candidate) in candidates.iter().enumerate() {
+        assert_eq!(Some(candidate), source.candidate(index as u128).as_ref());
+        assert_eq!(Some(candidate), source.iter_from(index as u128).next().as_ref());
+    }
+    let middle = candidates.len() / 2;
+    assert_eq!(candidates[middle..].to_vec(), source.iter_from(middle as u128).collect::<Vec<Vec<u8>>>());
+    assert_eq!(None, source.candidate(source.keyspace()));
+    assert_eq!(0, source.iter_from(source.keyspace()).count());
+    candidates
+}
+
+#[cfg(test)]
+pub fn as_strings<I: IntoIterator<Item = Vec<u8>>>(candidates: I) -> Vec<String> {
+    candidates.into_iter().map(|candidate| String::from_utf8(candidate).unwrap()).collect()
+}
+
+/**
+ * Fails if any candidate is listed twice.
+ */
+#[cfg(test)]
+pub fn assert_distinct<T: Clone + Ord>(candidates: &[T]) {
+    let mut distinct = candidates.to_vec();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(candidates.len(), distinct.len());
+}
@@ -0,0 +1,64 @@
+// rust-warpwallet as a library.
+// The binary in main.rs is only a consumer of what is exposed here, so anything it needs
+//      should be reachable through these modules.
+
+extern crate hex;
+extern crate rand;
+extern crate time;
+extern crate xor;
+
+pub mod bech32;
+pub mod bitcoin;
+pub mod coins;
+pub mod combinator;
+pub mod corpus;
+pub mod error;
+pub mod ethereum;
+pub mod hexxor;
+pub mod hybrid;
+pub mod index;
+pub mod keyspace;
+pub mod markov;
+pub mod mask;
+pub mod network;
+pub mod params;
+pub mod pbkdf2;
+pub mod profile;
+pub mod recovery;
+pub mod rules;
+pub mod salts;
+pub mod scrypt;
+pub mod search;
+pub mod sha256;
+pub mod targets;
+pub mod template;
+pub mod typos;
+pub mod warpwallet;
+pub mod wordlist;
+
+#[cfg(test)]
+mod threadtest;
+
+pub use bitcoin::{Address, KeyFormat, PrivateKeyWif, PublicKey, SecretExponent};
+pub use coins::CoinProfile;
+pub use combinator::{Combinator, Separator};
+pub use corpus::Corpus;
+pub use error::WarpError;
+pub use hybrid::{Hybrid, HybridSide};
+pub use ethereum::{EthereumAddress, KeystoreKdf};
+pub use index::{BloomFilter, HashIndex, IndexSummary};
+pub use keyspace::Keyspace;
+pub use markov::MarkovModel;
+pub use mask::Mask;
+pub use network::Network;
+pub use params::{CostEstimate, WarpParams};
+pub use profile::Profile;
+pub use recovery::{Recovery, RecoverySpec};
+pub use rules::{Rule, RuleSet};
+pub use salts::SaltVariants;
+pub use search::{SearchConfig, SearchEvent, Throughput};
+pub use targets::{Target, TargetKind, TargetMatch, TargetSet};
+pub use template::Template;
+pub use typos::Typos;
+pub use warpwallet::{DerivedKeypair, EncodedKey, WarpWallet};
+pub use wordlist::Wordlist;
@@ -1,59 +1,390 @@
 
 extern crate hex;
-extern crate xor;
-extern crate threadpool;
-extern crate time;
-extern crate rand;
-
-mod pbkdf2;
-mod scrypt;
-mod warpwallet;
-mod bitcoin;
-mod hexxor;
-mod sha256;
-mod threadtest;
-
-use time::PreciseTime;
-use rand::{thread_rng, Rng};
-use std::thread;
-
-use threadpool::ThreadPool;
-use std::sync::mpsc;
-use std::sync::mpsc::channel;
+extern crate rust_warpwallet;
+
+mod cli;
+
+use cli::{CombinatorOptions, Command, MarkovOptions, MaskOptions, Options};
+use rust_warpwallet::ethereum;
+use rust_warpwallet::markov::MarkovCandidates;
+use rust_warpwallet::profile::ProfileCandidates;
+use rust_warpwallet::search;
+use rust_warpwallet::{Combinator, Corpus, DerivedKeypair, HashIndex, Hybrid, Keyspace, KeystoreKdf, MarkovModel, Mask, Profile, Recovery, RecoverySpec, RuleSet, SaltVariants, SearchConfig, SearchEvent, Separator, TargetKind, TargetMatch, TargetSet, Template, Throughput, Typos, WarpError, WarpWallet, Wordlist};
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, Write};
+use std::process;
+use std::sync::Arc;
 
 // println! in tests are only visible with:
 //      cargo test -- --nocapture
 // or when running 1 test function
 //      cargo test name_of_function -- --nocapture
 
-const WARP_WALLET_SALT: &str = "a@b.c";
-const WARP_WALLET_SEARCH: &str = "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA";
+// Exit status of a search that found at least one target, 1 and 2 are failures and usage errors.
+const EXIT_MATCH_FOUND: i32 = 3;
 
 fn main() {
-    let start = PreciseTime::now();
-    let iterations = 50;
+    let options = match cli::parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    match options.command {
+        Command::Derive { ref passphrase } => derive(passphrase, &options),
+        Command::Search => search(&options),
+        Command::Estimate => estimate(&options),
+        Command::Index { ref addresses, ref output } => index(addresses, output)
+    }
+}
+
+fn index(addresses: &str, output: &str) {
+    let summary = File::open(addresses)
+        .map_err(WarpError::from)
+        .and_then(|file| HashIndex::build(io::BufReader::new(file), output));
+    match summary {
+        Ok(summary) => println!(
+            "indexed {} targets into [{}], {} duplicates, {} lines skipped",
+            summary.indexed, output, summary.duplicates, summary.skipped
+        ),
+        Err(error) => {
+            eprintln!("could not build the index: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+fn estimate(options: &Options) {
+    match options.params.estimate() {
+        Ok(estimate) => {
+            println!("memory per derivation: {} MiB", estimate.memory_bytes / (1024 * 1024));
+            println!("scrypt: {} seconds, pbkdf2: {} seconds", estimate.scrypt_time, estimate.pbkdf2_time);
+            println!("{} seconds per phrase.", estimate.wall_time());
+        },
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    }
+}
 
-    let (tx, rx): (mpsc::Sender<Vec<String>>, mpsc::Receiver<Vec<String>>) = mpsc::channel();
+fn derive(passphrase: &str, options: &Options) {
+    let wallet = WarpWallet::new(passphrase, &options.salt)
+        .with_params(options.params)
+        .with_coin(options.coin);
+    match wallet.derive() {
+        Ok(keypair) => {
+            println!("secret exponent [{}]", keypair.secret_exponent);
+            for format in &options.key_formats {
+                let key = keypair.key(*format);
+                println!("{} wif [{}]", format, key.wif);
+                println!("{} public key [{}]", format, key.public_key);
+                println!("{} addr [{}]", format, key.address);
+            }
+            for (label, address) in keypair.segwit_addresses() {
+                println!("{} addr [{}]", label, address);
+            }
+            println!("ethereum addr [{}]", keypair.ethereum);
+            if let Some(ref path) = options.eth_keystore {
+                if let Err(message) = write_eth_keystore(&keypair, path) {
+                    eprintln!("could not write the keystore: {}", message);
+                    process::exit(1);
+                }
+                println!("ethereum keystore written to [{}]", path);
+            }
+        },
+        Err(error) => {
+            eprintln!("derivation failed: {}", error);
+            process::exit(1);
+        }
+    }
+}
 
-    const SALT: [u8;5] = [97, 64, 98, 46, 99]; // a@b.c
+fn write_eth_keystore(keypair: &DerivedKeypair, path: &str) -> Result<(), String> {
+    eprint!("keystore password: ");
+    let mut password = String::new();
+    let stdin = io::stdin();
+    stdin.lock().read_line(&mut password).map_err(|error| error.to_string())?;
+    let password = password.trim_end_matches(&['\r', '\n'][..]);
+    if password.is_empty() {
+        return Err("the password can not be empty".to_owned());
+    }
 
-    for _inner_index in 0..iterations {
-        let cloned_tx = tx.clone();
-        thread::spawn(move || {
-            cloned_tx.send(warpwallet::print_phrase_wif_address_warp_wallet(generate_random_string(8).as_bytes().to_vec(), SALT.to_vec()));
+    let keystore = ethereum::keystore_v3(&keypair.secret_exponent, password, KeystoreKdf::standard())
+        .map_err(|error| error.to_string())?;
+    let mut file = File::create(path).map_err(|error| error.to_string())?;
+    file.write_all(keystore.as_bytes()).map_err(|error| error.to_string())
+}
+
+fn load_targets(options: &Options) -> Result<TargetSet, WarpError> {
+    let mut targets = TargetSet::from_addresses(&options.targets)?;
+    for path in &options.target_files {
+        targets.load_file(path)?;
+    }
+    for path in &options.target_indexes {
+        let index = HashIndex::open(path)?;
+        targets.add_index(match options.bloom_bits {
+            Some(bits) => index.with_bloom_filter(bits),
+            None => index
         });
     }
+    Ok(targets)
+}
 
-    for index in 0..iterations {
-        let result = rx.recv().unwrap();
-        println!("phrase [{}], wif [{}], addr [{}]", result[0], result[1], result[2]);
-        warpwallet::print_if_address_matches(&result[2]);
+/**
+ * Phrases are bytes, shown as text when they are UTF-8 and as hex otherwise.
+ */
+fn display_phrase(phrase: &[u8]) -> String {
+    match std::str::from_utf8(phrase) {
+        Ok(text) => text.to_owned(),
+        Err(_) => format!("0x{}", hex::encode(phrase))
     }
+}
+
+fn print_match(phrase: &[u8], salt: &[u8], found: &TargetMatch, options: &Options) {
+    println!("== TARGET FOUND == [{}] ({})", found.target.address, found.target.kind);
+    println!("    phrase [{}]", display_phrase(phrase));
+    // Debug escapes the whitespace salt variants end in.
+    println!("    salt {:?}", String::from_utf8_lossy(salt));
+    println!("    secret exponent [{}]", found.secret_exponent);
+    // An Ethereum key is its hex secret exponent, anything else is imported as WIF of the target's coin.
+    if found.target.kind == TargetKind::Ethereum {
+        return;
+    }
+    match found.secret_exponent.to_wif(found.key_format.is_compressed(), found.target.coin().unwrap_or(options.coin)) {
+        Ok(wif) => println!("    {} wif [{}]", found.key_format, wif),
+        Err(error) => println!("    no wif: {}", error)
+    }
+}
 
-    let end = PreciseTime::now();
-    let run_time = start.to(end);
-    println!("{} seconds for this round.", run_time);
-    println!("That's {} seconds per phrase.", run_time/iterations);
+fn print_throughput(throughput: &Throughput) {
+    println!(
+        "{} phrases in {} seconds, {:.2} phrases per second, {} found",
+        throughput.candidates, throughput.elapsed.num_seconds(), throughput.per_second(), throughput.found
+    );
+}
+
+fn build_mask(options: &MaskOptions) -> Result<Mask, WarpError> {
+    let mask = Mask::parse(&options.mask, &options.custom_charsets)?;
+    match options.lengths {
+        Some((min_length, max_length)) => {
+            let length = mask.positions();
+            mask.with_lengths(min_length.unwrap_or(1), max_length.unwrap_or(length))
+        },
+        None => Ok(mask)
+    }
+}
+
+fn load_combinator(options: &CombinatorOptions, separators: &[Separator]) -> Result<Combinator, WarpError> {
+    let combinator = Combinator::load(&options.wordlists)?.with_separators(separators.to_vec())?;
+    match (options.min_words, options.max_words) {
+        (None, None) => Ok(combinator),
+        (min_words, max_words) => {
+            let min_words = min_words.unwrap_or_else(|| options.wordlists.len().max(2).min(max_words.unwrap_or(usize::MAX)));
+            combinator.with_word_counts(min_words, max_words.unwrap_or(min_words))
+        }
+    }
+}
+
+fn load_recovery(path: &str, separators: &[Separator]) -> Result<Recovery, WarpError> {
+    Recovery::new(RecoverySpec::load_file(path)?)?.with_separators(separators.to_vec())
+}
+
+fn load_template(template: &str, lists: &[(String, String)]) -> Result<Template, WarpError> {
+    let mut words = HashMap::new();
+    for (name, path) in lists {
+        words.insert(name.clone(), Wordlist::open(vec![path]).collect::<Result<Vec<Vec<u8>>, WarpError>>()?);
+    }
+    Template::parse(template, &words)
+}
+
+fn train_markov(options: &MarkovOptions) -> Result<MarkovModel, WarpError> {
+    MarkovModel::train(Wordlist::open(&options.wordlists), options.order)
+}
+
+/**
+ * The likeliest phrases of the model, down to the threshold and up to the budget, whichever are given.
+ */
+fn markov_candidates<'a>(model: &'a MarkovModel, options: &MarkovOptions) -> Result<MarkovCandidates<'a>, WarpError> {
+    let mut candidates = model.candidates(options.min_length, options.max_length)?;
+    if let Some(budget) = options.budget {
+        candidates = candidates.with_budget(budget);
+    }
+    if let Some(threshold) = options.threshold {
+        candidates = candidates.with_threshold(threshold);
+    }
+    Ok(candidates)
+}
+
+fn load_profile(path: &str, separators: &[Separator]) -> Result<ProfileCandidates, WarpError> {
+    Profile::load_file(path)?.candidates(separators.to_vec())
+}
+
+fn load_rules(options: &Options) -> Result<RuleSet, WarpError> {
+    let mut rules = RuleSet::new();
+    for rule in &options.rules {
+        rules.add(rule)?;
+    }
+    for path in &options.rule_files {
+        rules.load_file(path)?;
+    }
+    Ok(rules)
+}
+
+/**
+ * Exits with a usage error when a candidate source given on the command line can not be loaded.
+ */
+fn loaded<T>(source: Option<Result<T, WarpError>>, what: &str) -> Option<T> {
+    match source {
+        Some(Ok(source)) => Some(source),
+        Some(Err(error)) => {
+            eprintln!("{}: {}", what, error);
+            process::exit(2);
+        },
+        None => None
+    }
+}
+
+fn search(options: &Options) {
+    let mut mask = loaded(options.mask.as_ref().map(build_mask), "invalid --mask");
+    let hybrid = match options.hybrid {
+        Some(side) => loaded(mask.take().map(|mask| Hybrid::load(&options.wordlists, mask, side)), "could not load the --hybrid wordlists"),
+        None => None
+    };
+    let combinator = loaded(
+        options.combinator.as_ref().map(|combinator| load_combinator(combinator, &options.separators)),
+        "could not load the --combine wordlists"
+    );
+    let recovery = loaded(options.recover.as_ref().map(|path| load_recovery(path, &options.separators)), "could not load the --recover spec");
+    let template = loaded(
+        options.template.as_ref().map(|template| load_template(template, &options.template_lists)),
+        "could not load the --template"
+    );
+    let typos = loaded(options.typos.as_ref().map(|phrase| Typos::new(phrase.as_bytes(), options.max_edits)), "invalid --typos");
+    let model = loaded(options.markov.as_ref().map(train_markov), "could not train the --markov model");
+    let markov = loaded(
+        options.markov.as_ref().zip(model.as_ref()).map(|(markov, model)| markov_candidates(model, markov)),
+        "invalid --markov lengths"
+    );
+    let profile = loaded(options.profile.as_ref().map(|path| load_profile(path, &options.separators)), "could not load the --profile");
+    let corpus = loaded(
+        options.corpus.as_ref().map(|corpus| {
+            let label = format!("trying every run of {} to {} words of the corpus", corpus.min_words, corpus.max_words);
+            Corpus::open(&corpus.files, corpus.min_words, corpus.max_words).map(|candidates| (label, candidates))
+        }),
+        "invalid --corpus"
+    );
+    let rules = match load_rules(options) {
+        Ok(rules) => rules,
+        Err(error) => {
+            eprintln!("could not load the rules: {}", error);
+            process::exit(2);
+        }
+    };
+
+    // The sources with numbered candidates, the ones --keyspace and --skip work on.
+    let numbered: Option<(String, &dyn Keyspace)> = if let Some(ref hybrid) = hybrid {
+        Some(("hybrid".to_owned(), hybrid))
+    } else if let Some(ref mask) = mask {
+        Some(("mask".to_owned(), mask))
+    } else if let Some(ref combinator) = combinator {
+        Some(("combinator".to_owned(), combinator))
+    } else if let Some(ref template) = template {
+        Some(("template".to_owned(), template))
+    } else if let Some(ref recovery) = recovery {
+        Some((format!("{} orders of the remembered words,", recovery.orders()), recovery))
+    } else {
+        None
+    };
+    // The sources that make their candidates likeliest first, with what they are trying.
+    type Streamed<'a> = (String, Box<dyn Iterator<Item = Vec<u8>> + 'a>);
+    let streamed: Option<Streamed<'_>> = if let Some(typos) = typos {
+        Some((format!("trying phrases up to {} slips away from the remembered one", options.max_edits), Box::new(typos)))
+    } else if let Some(markov) = markov {
+        Some(("trying the likeliest phrases of the Markov model".to_owned(), Box::new(markov.map(|(phrase, _)| phrase))))
+    } else if let Some(profile) = profile {
+        Some(("trying the profile details with dates, in a few cases and with every separator".to_owned(), Box::new(profile)))
+    } else {
+        None
+    };
+    if options.keyspace {
+        if let Some((_, source)) = numbered {
+            println!("{}", source.keyspace());
+        }
+        return;
+    }
+
+    let targets = match load_targets(options) {
+        Ok(ref targets) if targets.is_empty() => {
+            eprintln!("search needs at least one --target, --targets or --target-index\n\n{}", cli::USAGE);
+            process::exit(2);
+        },
+        Ok(targets) => targets,
+        Err(error) => {
+            eprintln!("could not load the targets: {}", error);
+            process::exit(2);
+        }
+    };
+    println!("searching for {} targets", targets.len());
+
+    let mut config = SearchConfig::new(options.salt.as_bytes(), options.params);
+    config.threads = options.threads;
+    if options.salt_variants {
+        match SaltVariants::new(&options.salt) {
+            Ok(variants) => config.salts = variants.with_plus_tags(&options.plus_tags).variants(),
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(2);
+            }
+        }
+        println!("deriving every phrase with {} variants of the salt", config.salts.len());
+    }
+    let candidates: Box<dyn Iterator<Item = Result<Vec<u8>, WarpError>> + '_> = match (numbered, streamed, corpus) {
+        (Some((label, source)), _, _) => {
+            println!("{} keyspace {}, starting at {}", label, source.keyspace(), options.skip);
+            Box::new(source.iter_from(options.skip).map(Ok))
+        },
+        (None, Some((label, candidates)), _) => {
+            println!("{}", label);
+            Box::new(candidates.map(Ok))
+        },
+        (None, None, Some((label, corpus))) => {
+            println!("{}", label);
+            Box::new(corpus)
+        },
+        _ if !options.wordlists.is_empty() && !rules.is_empty() => {
+            println!("mangling every word with {} rules", rules.len());
+            Box::new(rules.mangle(Wordlist::open(&options.wordlists)))
+        },
+        _ if !options.wordlists.is_empty() => Box::new(Wordlist::open(&options.wordlists)),
+        _ => {
+            eprintln!("search needs a --markov model, --wordlist, --mask, --combine, --recover, --typos, --template, --profile or --corpus\n\n{}", cli::USAGE);
+            process::exit(2);
+        }
+    };
+
+    let result = search::run(candidates, Arc::new(targets), &config, |event| match event {
+        SearchEvent::Found { candidate, salt, target_match } => print_match(&candidate, &salt, &target_match, options),
+        SearchEvent::Failed { candidate, salt, error } =>
+            println!("phrase [{}] with salt {:?} failed: {}", display_phrase(&candidate), String::from_utf8_lossy(&salt), error),
+        SearchEvent::Progress(throughput) => print_throughput(&throughput)
+    });
+    match result {
+        Ok(throughput) => {
+            print_throughput(&throughput);
+            if throughput.found > 0 {
+                process::exit(EXIT_MATCH_FOUND);
+            }
+        },
+        Err(error) => {
+            eprintln!("search stopped: {}", error);
+            process::exit(1);
+        }
+    }
 }
 
 // bitcoin: 1Awesome4ZhNYmUp5PApkz1qQMVkkVYLhA
@@ -80,24 +411,6 @@ fn main() {
 // ___$$$$$$$$$$$$$$$$$$$$$$$$
 // _____$$$$$$$$$$$$$$$$$$$$__
 
-fn generate_random_string(char_length: usize) -> String {
-    thread::spawn(move || {
-        thread_rng()
-            .gen_ascii_chars()
-            .take(char_length)
-            .collect::<String>()
-    }).join().unwrap()
-}
-
-#[test]
-fn test_generate_random_string() {
-    let char_length = 8;
-    for _i in 0..10 {
-        let random_string = generate_random_string(char_length);
-        assert_eq!(char_length, random_string.len());
-    }
-}
-
 #[test]
 fn test_from_hex() {
     assert_eq!(
@@ -0,0 +1,271 @@
+// A character Markov chain trained on a wordlist, for brute force that tries the likeliest phrases
+// first. The probability of a phrase is the product of the probability of each byte given the bytes
+// before it (at most `order` of them) and of the phrase ending there, as counted in the training words.
+// Only transitions seen in training are followed.
+//
+// Candidates come out in descending probability: a best first search keeps every unfinished prefix
+// in a heap, and as extending a prefix can only lower its probability, the most probable entry
+// popped is never beaten by anything still to come. The heap grows by up to 257 entries per prefix
+// popped, so a candidate budget or probability threshold is what keeps memory in check.
+
+use error::WarpError;
+#[cfg(test)]
+use keyspace;
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+pub const MAX_ORDER: usize = 8;
+
+/// Where a context can go next, as natural logarithms of probabilities.
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    next: Vec<(u8, f64)>,
+    /// NEG_INFINITY if no training word ended in this context.
+    end: f64
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkovModel {
+    order: usize,
+    /// Keyed by the last `order` bytes, or all of them at the start of a phrase.
+    states: HashMap<Vec<u8>, State>
+}
+
+impl MarkovModel {
+    /**
+     * Counts the transitions of every training word, stopping at the first error of the source.
+     */
+    pub fn train<I>(words: I, order: usize) -> Result<MarkovModel, WarpError>
+        where I: IntoIterator<Item = Result<Vec<u8>, WarpError>> {
+        if order == 0 || order > MAX_ORDER {
+            return Err(WarpError::InvalidPattern(format!("a Markov order is 1 to {}", MAX_ORDER)));
+        }
+        let mut counts: HashMap<Vec<u8>, (HashMap<u8, u64>, u64)> = HashMap::new();
+        for word in words {
+            let word = word?;
+            for length in 0..=word.len() {
+                let context = word[length.saturating_sub(order)..length].to_vec();
+                let state = counts.entry(context).or_insert_with(|| (HashMap::new(), 0));
+                match word.get(length) {
+                    Some(byte) => *state.0.entry(*byte).or_insert(0) += 1,
+                    None => state.1 += 1
+                }
+            }
+        }
+        if counts.is_empty() {
+            return Err(WarpError::InvalidPattern("a Markov model needs at least one training word".to_owned()));
+        }
+
+        let states = counts.into_iter().map(|(context, (next, end))| {
+            let total = (next.values().sum::<u64>() + end) as f64;
+            let mut next: Vec<(u8, f64)> = next.into_iter().map(|(byte, count)| (byte, (count as f64 / total).ln())).collect();
+            next.sort_by(|a, b| b.1.partial_cmp(&a.1).expect("probabilities are never NaN").then(a.0.cmp(&b.0)));
+            let end = if end > 0 { (end as f64 / total).ln() } else { f64::NEG_INFINITY };
+            (context, State { next, end })
+        }).collect();
+        Ok(MarkovModel { order, states })
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    fn state(&self, phrase: &[u8]) -> Option<&State> {
+        self.states.get(&phrase[phrase.len().saturating_sub(self.order)..])
+    }
+
+    /**
+     * The probability the model gives `phrase`, 0 if it takes a transition never seen in training.
+     */
+    pub fn probability(&self, phrase: &[u8]) -> f64 {
+        let mut log_probability = 0.0;
+        for length in 0..phrase.len() {
+            let byte = phrase[length];
+            let next = self.state(&phrase[..length])
+                .and_then(|state| state.next.iter().find(|next| next.0 == byte));
+            match next {
+                Some(&(_, log)) => log_probability += log,
+                None => return 0.0
+            }
+        }
+        match self.state(phrase) {
+            Some(state) => (log_probability + state.end).exp(),
+            None => 0.0
+        }
+    }
+
+    /**
+     * Every phrase of `min_length` to `max_length` bytes the model can make, most probable first.
+     */
+    pub fn candidates(&self, min_length: usize, max_length: usize) -> Result<MarkovCandidates<'_>, WarpError> {
+        if min_length == 0 || min_length > max_length {
+            return Err(WarpError::InvalidPattern(format!("can not make phrases of {} to {} bytes", min_length, max_length)));
+        }
+        let mut heap = BinaryHeap::new();
+        heap.push(Partial { log_probability: 0.0, phrase: vec![], complete: false });
+        Ok(MarkovCandidates { model: self, min_length, max_length, heap, log_threshold: f64::NEG_INFINITY, budget: None })
+    }
+}
+
+/// A prefix still to extend, or a finished phrase, on the search heap.
+#[derive(Debug)]
+struct Partial {
+    log_probability: f64,
+    phrase: Vec<u8>,
+    complete: bool
+}
+
+impl Ord for Partial {
+    /**
+     * The more probable first, then a finished phrase before a prefix, then the shorter and lower bytes.
+     */
+    fn cmp(&self, other: &Partial) -> Ordering {
+        self.log_probability.partial_cmp(&other.log_probability).unwrap_or(Ordering::Equal)
+            .then(self.complete.cmp(&other.complete))
+            .then_with(|| other.phrase.len().cmp(&self.phrase.len()))
+            .then_with(|| other.phrase.cmp(&self.phrase))
+    }
+}
+
+impl PartialOrd for Partial {
+    fn partial_cmp(&self, other: &Partial) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Partial {
+    fn eq(&self, other: &Partial) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Partial {}
+
+/// Phrases of a model with their probability, in descending probability.
+pub struct MarkovCandidates<'a> {
+    model: &'a MarkovModel,
+    min_length: usize,
+    max_length: usize,
+    heap: BinaryHeap<Partial>,
+    log_threshold: f64,
+    budget: Option<u64>
+}
+
+impl<'a> MarkovCandidates<'a> {
+    /**
+     * Stops at the first phrase less probable than `threshold`.
+     */
+    pub fn with_threshold(self, threshold: f64) -> MarkovCandidates<'a> {
+        MarkovCandidates { log_threshold: threshold.ln(), ..self }
+    }
+
+    /**
+     * Stops after `budget` phrases.
+     */
+    pub fn with_budget(self, budget: u64) -> MarkovCandidates<'a> {
+        MarkovCandidates { budget: Some(budget), ..self }
+    }
+}
+
+impl<'a> Iterator for MarkovCandidates<'a> {
+    type Item = (Vec<u8>, f64);
+
+    fn next(&mut self) -> Option<(Vec<u8>, f64)> {
+        if self.budget == Some(0) {
+            return None;
+        }
+        while let Some(partial) = self.heap.pop() {
+            if partial.log_probability < self.log_threshold {
+                self.heap.clear();
+                return None;
+            }
+            if partial.complete {
+                self.budget = self.budget.map(|budget| budget - 1);
+                return Some((partial.phrase, partial.log_probability.exp()));
+            }
+            let state = match self.model.state(&partial.phrase) {
+                Some(state) => state,
+                None => continue
+            };
+            if partial.phrase.len() >= self.min_length && state.end > f64::NEG_INFINITY {
+                self.heap.push(Partial { log_probability: partial.log_probability + state.end, phrase: partial.phrase.clone(), complete: true });
+            }
+            if partial.phrase.len() < self.max_length {
+                for &(byte, log) in &state.next {
+                    let mut phrase = partial.phrase.clone();
+                    phrase.push(byte);
+                    self.heap.push(Partial { log_probability: partial.log_probability + log, phrase, complete: false });
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+fn test_model(words: &[&str], order: usize) -> MarkovModel {
+    MarkovModel::train(words.iter().map(|word| Ok(word.as_bytes().to_vec())), order).unwrap()
+}
+
+#[cfg(test)]
+fn phrases(candidates: MarkovCandidates) -> Vec<String> {
+    candidates.map(|(phrase, _)| String::from_utf8(phrase).unwrap()).collect()
+}
+
+#[test]
+fn test_markov_order() {
+    // "" goes to a 3/4, b 1/4. After a: a 2/5, b 1/5, end 2/5. After b: end.
+    let model = test_model(&["aa", "ab", "aa", "b"], 1);
+    let vectors: Vec<(&str, f64)> = vec![
+        // Phrase, probability
+        ("a", 0.3),
+        ("b", 0.25),
+        ("ab", 0.15),
+        ("aa", 0.12),
+        ("aab", 0.06),
+        ("aaa", 0.048),
+        ("ba", 0.0),
+        ("c", 0.0)
+    ];
+    for tuple in vectors {
+        println!("Testing probability of [{}]", tuple.0);
+        assert!((model.probability(tuple.0.as_bytes()) - tuple.1).abs() < 1e-12);
+    }
+
+    let candidates: Vec<(Vec<u8>, f64)> = model.candidates(1, 3).unwrap().collect();
+    assert_eq!(vec!["a", "b", "ab", "aa", "aab", "aaa"], candidates.iter().map(|c| String::from_utf8(c.0.clone()).unwrap()).collect::<Vec<String>>());
+    for candidate in &candidates {
+        assert!((model.probability(&candidate.0) - candidate.1).abs() < 1e-12);
+    }
+    assert_eq!(vec!["ab", "aa"], phrases(model.candidates(2, 2).unwrap()));
+    assert_eq!(vec!["a", "b"], phrases(model.candidates(1, 3).unwrap().with_threshold(0.2)));
+    assert_eq!(vec!["a", "b", "ab"], phrases(model.candidates(1, 3).unwrap().with_budget(3)));
+    assert_eq!(0, model.candidates(1, 3).unwrap().with_budget(0).count());
+}
+
+#[test]
+fn test_markov_descending() {
+    let words = ["password", "passw0rd", "letmein", "dragon", "monkey", "master", "hunter2", "shadow", "sunshine", "princess"];
+    for order in 1..4 {
+        println!("Testing Markov order {}", order);
+        let model = test_model(&words, order);
+        assert_eq!(order, model.order());
+        let candidates: Vec<(Vec<u8>, f64)> = model.candidates(4, 10).unwrap().with_budget(2_000).collect();
+        assert!(candidates.len() > words.len());
+        for pair in candidates.windows(2) {
+            assert!(pair[0].1 >= pair[1].1);
+        }
+        keyspace::assert_distinct(&candidates.iter().map(|candidate| candidate.0.clone()).collect::<Vec<Vec<u8>>>());
+        assert!(candidates.iter().all(|candidate| candidate.0.len() >= 4 && candidate.0.len() <= 10));
+    }
+    assert_eq!(2_000, test_model(&words, 1).candidates(4, 10).unwrap().with_budget(2_000).count());
+    // A high enough order only knows its training words.
+    let model = test_model(&words, 8);
+    assert_eq!(words.len(), model.candidates(1, 20).unwrap().count());
+
+    assert!(MarkovModel::train(vec![], 2).is_err());
+    assert!(MarkovModel::train(vec![Ok(b"cow".to_vec())], 0).is_err());
+    assert!(MarkovModel::train(vec![Ok(b"cow".to_vec()), Err(WarpError::Io("broken".to_owned()))], 2).is_err());
+    assert!(model.candidates(0, 4).is_err());
+    assert!(model.candidates(5, 4).is_err());
+}
@@ -0,0 +1,289 @@
+// Hashcat style masks: every position of the passphrase gets its own charset.
+//      ?l a-z  ?u A-Z  ?d 0-9  ?s the 33 printable ASCII specials, space included  ?a ?l?u?d?s
+//      ?h 0-9a-f  ?H 0-9A-F  ?b every byte  ?? a literal ?  ?1 to ?4 the custom charsets
+// Anything else is a literal. The keyspace is enumerated like an odometer, the last position
+// turning fastest, so index n is always the same candidate and a search can resume from any index.
+
+use error::WarpError;
+use keyspace::{self, Keyspace};
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SPECIALS: &[u8] = b" !\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+const HEX_LOWER: &[u8] = b"0123456789abcdef";
+const HEX_UPPER: &[u8] = b"0123456789ABCDEF";
+pub const CUSTOM_CHARSETS: usize = 4;
+
+fn built_in_charset(name: u8) -> Option<Vec<u8>> {
+    match name {
+        b'l' => Some(LOWER.to_vec()),
+        b'u' => Some(UPPER.to_vec()),
+        b'd' => Some(DIGITS.to_vec()),
+        b's' => Some(SPECIALS.to_vec()),
+        b'a' => Some([LOWER, UPPER, DIGITS, SPECIALS].concat()),
+        b'h' => Some(HEX_LOWER.to_vec()),
+        b'H' => Some(HEX_UPPER.to_vec()),
+        b'b' => Some((0..=255).collect()),
+        b'?' => Some(vec![b'?']),
+        _ => None
+    }
+}
+
+/**
+ * Splits a mask, or a custom charset definition, into one charset per ? placeholder or literal byte.
+ */
+fn parse_placeholders(mask: &str, custom: &[Option<Vec<u8>>]) -> Result<Vec<Vec<u8>>, WarpError> {
+    let bytes = mask.as_bytes();
+    let mut positions = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'?' {
+            positions.push(vec![bytes[index]]);
+            index += 1;
+            continue;
+        }
+        let name = *bytes.get(index + 1)
+            .ok_or_else(|| WarpError::InvalidPattern(format!("{} ends in a lone ?", mask)))?;
+        let charset = match name {
+            b'1'..=b'4' => custom.get((name - b'1') as usize).cloned().and_then(|charset| charset)
+                .ok_or_else(|| WarpError::InvalidPattern(format!("?{} is not defined", name as char)))?,
+            _ => built_in_charset(name)
+                .ok_or_else(|| WarpError::InvalidPattern(format!("unknown charset ?{}", name as char)))?
+        };
+        positions.push(charset);
+        index += 2;
+    }
+    Ok(positions)
+}
+
+/**
+ * A custom charset, -1 ?l?d or -1 abc, as the distinct bytes it names, in order.
+ */
+pub fn parse_charset(definition: &str) -> Result<Vec<u8>, WarpError> {
+    let mut charset: Vec<u8> = vec![];
+    for byte in parse_placeholders(definition, &[])?.into_iter().flatten() {
+        if !charset.contains(&byte) {
+            charset.push(byte);
+        }
+    }
+    if charset.is_empty() {
+        return Err(WarpError::InvalidPattern("a custom charset can not be empty".to_owned()));
+    }
+    Ok(charset)
+}
+
+/// A parsed mask and the range of lengths to enumerate, each length a prefix of the mask.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    positions: Vec<Vec<u8>>,
+    min_length: usize,
+    max_length: usize
+}
+
+impl Mask {
+    /**
+     * Custom charset definitions are given in order, the first one is ?1. An empty definition
+     * leaves that charset undefined, so ?2 can be defined without ?1.
+     */
+    pub fn parse<S: AsRef<str>>(mask: &str, custom_charsets: &[S]) -> Result<Mask, WarpError> {
+        if custom_charsets.len() > CUSTOM_CHARSETS {
+            return Err(WarpError::InvalidPattern(format!("at most {} custom charsets", CUSTOM_CHARSETS)));
+        }
+        let custom = custom_charsets.iter()
+            .map(|definition| match definition.as_ref() {
+                "" => Ok(None),
+                definition => parse_charset(definition).map(Some)
+            })
+            .collect::<Result<Vec<Option<Vec<u8>>>, WarpError>>()?;
+        let positions = parse_placeholders(mask, &custom)?;
+        if positions.is_empty() {
+            return Err(WarpError::InvalidPattern("the mask is empty".to_owned()));
+        }
+        let length = positions.len();
+        Mask::checked(Mask { positions, min_length: length, max_length: length })
+    }
+
+    /**
+     * Enumerates every length from min to max, like hashcat --increment: the candidates of
+     * length n come from the first n positions of the mask, shorter lengths first.
+     */
+    pub fn with_lengths(self, min_length: usize, max_length: usize) -> Result<Mask, WarpError> {
+        if min_length == 0 || min_length > max_length || max_length > self.positions.len() {
+            return Err(WarpError::InvalidPattern(format!(
+                "lengths {} to {} do not fit a mask of {} positions", min_length, max_length, self.positions.len()
+            )));
+        }
+        Mask::checked(Mask { min_length, max_length, ..self })
+    }
+
+    /**
+     * The number of positions, the longest length the mask can produce.
+     */
+    pub fn positions(&self) -> usize {
+        self.positions.len()
+    }
+
+    fn checked(mask: Mask) -> Result<Mask, WarpError> {
+        let mut total: u128 = 0;
+        for length in mask.min_length..=mask.max_length {
+            total = mask.length_keyspace(length).and_then(|keyspace| total.checked_add(keyspace))
+                .ok_or_else(keyspace::overflow)?;
+        }
+        Ok(mask)
+    }
+
+    fn length_keyspace(&self, length: usize) -> Option<u128> {
+        self.positions[..length].iter().try_fold(1u128, |total, charset| total.checked_mul(charset.len() as u128))
+    }
+
+    /**
+     * The per position charset indices of candidate `index`.
+     */
+    fn digits(&self, mut index: u128) -> Option<Vec<usize>> {
+        for length in self.min_length..=self.max_length {
+            let keyspace = self.length_keyspace(length).expect("checked when the mask was built");
+            if index >= keyspace {
+                index -= keyspace;
+                continue;
+            }
+            let mut digits = vec![0; length];
+            for position in (0..length).rev() {
+                let radix = self.positions[position].len() as u128;
+                digits[position] = (index % radix) as usize;
+                index /= radix;
+            }
+            return Some(digits);
+        }
+        None
+    }
+
+    fn render(&self, digits: &[usize]) -> Vec<u8> {
+        digits.iter().enumerate().map(|(position, digit)| self.positions[position][*digit]).collect()
+    }
+}
+
+impl Keyspace for Mask {
+    /**
+     * Over all lengths.
+     */
+    fn keyspace(&self) -> u128 {
+        (self.min_length..=self.max_length)
+            .map(|length| self.length_keyspace(length).expect("checked when the mask was built"))
+            .sum()
+    }
+
+    fn candidate(&self, index: u128) -> Option<Vec<u8>> {
+        self.digits(index).map(|digits| self.render(&digits))
+    }
+
+    fn iter_from(&self, start: u128) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        Box::new(MaskIter { mask: self, digits: self.digits(start) })
+    }
+}
+
+/// Walks a mask's keyspace without recomputing each candidate from its index.
+struct MaskIter<'a> {
+    mask: &'a Mask,
+    digits: Option<Vec<usize>>
+}
+
+impl<'a> Iterator for MaskIter<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let candidate = self.mask.render(self.digits.as_ref()?);
+        let mask = self.mask;
+        let digits = self.digits.as_mut().expect("checked above");
+        let mut position = digits.len();
+        loop {
+            if position == 0 {
+                // Every position wrapped, go on with the next length.
+                let length = digits.len() + 1;
+                self.digits = if length <= mask.max_length { Some(vec![0; length]) } else { None };
+                break;
+            }
+            position -= 1;
+            digits[position] += 1;
+            if digits[position] < mask.positions[position].len() {
+                break;
+            }
+            digits[position] = 0;
+        }
+        Some(candidate)
+    }
+}
+
+#[test]
+fn test_mask_keyspace() {
+    let vectors: Vec<(&str, Vec<&str>, u128)> = vec![
+        // Mask, custom charsets, keyspace
+        ("?l", vec![], 26),
+        ("?u?d", vec![], 260),
+        ("?s", vec![], 33),
+        ("?a?a", vec![], 95 * 95),
+        ("?h?H?b", vec![], 16 * 16 * 256),
+        ("pass?d?d", vec![], 100),
+        ("??", vec![], 1),
+        ("?1?2", vec!["?l?d", "abcabc"], 36 * 3),
+        ("?2?d", vec!["", "?u"], 260),
+        ("é?d", vec![], 10)
+    ];
+    for tuple in vectors {
+        println!("Testing mask [{}]", tuple.0);
+        let mask = Mask::parse(tuple.0, &tuple.1).unwrap();
+        assert_eq!(tuple.2, mask.keyspace());
+        assert_eq!(tuple.2, mask.iter().count() as u128);
+    }
+}
+
+#[test]
+fn test_mask_candidates() {
+    let mask = Mask::parse("?1x?d", &["ab"]).unwrap();
+    let candidates = keyspace::as_strings(mask.iter());
+    assert_eq!(20, candidates.len());
+    assert_eq!("ax0", candidates[0]);
+    assert_eq!("ax9", candidates[9]);
+    assert_eq!("bx0", candidates[10]);
+    assert_eq!("bx9", candidates[19]);
+    assert_eq!(candidates, keyspace::as_strings(keyspace::assert_numbering(&mask)));
+    assert_eq!(vec![b"bx8".to_vec(), b"bx9".to_vec()], mask.iter_from(18).collect::<Vec<Vec<u8>>>());
+}
+
+#[test]
+fn test_mask_increment() {
+    let mask = Mask::parse("?d?l?l", &[] as &[&str]).unwrap().with_lengths(1, 3).unwrap();
+    assert_eq!(3, mask.positions());
+    assert_eq!(10 + 260 + 6_760, mask.keyspace());
+    let candidates = keyspace::assert_numbering(&mask);
+    assert_eq!(b"0".to_vec(), candidates[0]);
+    assert_eq!(b"9".to_vec(), candidates[9]);
+    assert_eq!(b"0a".to_vec(), candidates[10]);
+    assert_eq!(b"9zz".to_vec(), candidates[candidates.len() - 1]);
+    assert_eq!(Some(b"0aa".to_vec()), mask.candidate(270));
+    assert_eq!(candidates[265..].to_vec(), mask.iter_from(265).collect::<Vec<Vec<u8>>>());
+
+    let mask = Mask::parse("?d?d", &[] as &[&str]).unwrap();
+    assert!(mask.clone().with_lengths(0, 2).is_err());
+    assert!(mask.clone().with_lengths(2, 1).is_err());
+    assert!(mask.with_lengths(1, 3).is_err());
+}
+
+#[test]
+fn test_invalid_masks() {
+    let vectors: Vec<(&str, Vec<&str>)> = vec![
+        ("", vec![]),
+        ("abc?", vec![]),
+        ("?x", vec![]),
+        ("?1", vec![]),
+        ("?2", vec!["?d"]),
+        ("?1", vec![""]),
+        ("?d", vec!["a", "b", "c", "d", "e"]),
+        ("?b?b?b?b?b?b?b?b?b?b?b?b?b?b?b?b", vec![])
+    ];
+    for tuple in vectors {
+        println!("Testing invalid mask [{}] {:?}", tuple.0, tuple.1);
+        assert!(Mask::parse(tuple.0, &tuple.1).is_err());
+    }
+    assert!(Mask::parse("?b?b?b?b?b?b?b?b?b?b?b?b?b?b?b", &[] as &[&str]).is_ok());
+}
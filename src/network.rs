@@ -0,0 +1,108 @@
+// The bitcoin networks keys and addresses can be encoded for.
+// Testnet, signet and regtest share their base58 version bytes, only the bech32 hrp of regtest differs.
+
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Network {
+    #[default]
+    Bitcoin,
+    Testnet,
+    Signet,
+    Regtest
+}
+
+impl Network {
+    pub const ALL: [Network; 4] = [Network::Bitcoin, Network::Testnet, Network::Signet, Network::Regtest];
+
+    /**
+     * First byte of a WIF payload: 5, K or L on mainnet, 9 or c on the test networks.
+     */
+    pub fn wif_version(self) -> u8 {
+        match self {
+            Network::Bitcoin => 0x80,
+            _ => 0xef
+        }
+    }
+
+    /**
+     * Version byte of P2PKH addresses: 1... on mainnet, m... or n... on the test networks.
+     */
+    pub fn p2pkh_version(self) -> u8 {
+        match self {
+            Network::Bitcoin => 0x00,
+            _ => 0x6f
+        }
+    }
+
+    /**
+     * Version byte of P2SH addresses: 3... on mainnet, 2... on the test networks.
+     */
+    pub fn p2sh_version(self) -> u8 {
+        match self {
+            Network::Bitcoin => 0x05,
+            _ => 0xc4
+        }
+    }
+
+    /**
+     * Human readable part of segwit addresses.
+     */
+    pub fn bech32_hrp(self) -> &'static str {
+        match self {
+            Network::Bitcoin => "bc",
+            Network::Testnet | Network::Signet => "tb",
+            Network::Regtest => "bcrt"
+        }
+    }
+
+    /**
+     * A WIF does not tell the test networks apart, so 0xef comes back as Testnet.
+     */
+    pub fn from_wif_version(version: u8) -> Option<Network> {
+        Network::ALL.iter().cloned().find(|network| network.wif_version() == version)
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(network: &str) -> Result<Network, String> {
+        match network {
+            "bitcoin" | "mainnet" => Ok(Network::Bitcoin),
+            "testnet" => Ok(Network::Testnet),
+            "signet" => Ok(Network::Signet),
+            "regtest" => Ok(Network::Regtest),
+            _ => Err(format!("unknown network {}", network))
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Network::Bitcoin => write!(f, "bitcoin"),
+            Network::Testnet => write!(f, "testnet"),
+            Network::Signet => write!(f, "signet"),
+            Network::Regtest => write!(f, "regtest")
+        }
+    }
+}
+
+#[test]
+fn test_network_from_str_and_display() {
+    for network in &Network::ALL {
+        println!("Testing network [{}]", network);
+        assert_eq!(Ok(*network), network.to_string().parse());
+    }
+    assert_eq!(Ok(Network::Bitcoin), "mainnet".parse());
+    assert!("litecoin".parse::<Network>().is_err());
+}
+
+#[test]
+fn test_wif_version_round_trip() {
+    assert_eq!(Some(Network::Bitcoin), Network::from_wif_version(0x80));
+    assert_eq!(Some(Network::Testnet), Network::from_wif_version(0xef));
+    assert_eq!(None, Network::from_wif_version(0x00));
+}
@@ -0,0 +1,179 @@
+// The cost settings of a warp derivation.
+//
+//    s1 	=	scrypt(key=(passphrase||scrypt_concat), salt=(salt||scrypt_concat), N, r, p, dkLen)
+//    s2 	=	pbkdf2(key=(passphrase||pbkdf2_concat), salt=(salt||pbkdf2_concat), c, dkLen, prf=HMAC_SHA256)
+
+use error::WarpError;
+use pbkdf2::perform_pbkdf2;
+use scrypt::{perform_scrypt, scrypt_memory_bytes, validate_scrypt_params};
+use std::cmp;
+use time::{Duration, PreciseTime};
+
+const WARP_PBKDF2_CONCAT: u8 = 2u8;
+// 2^16
+const WARP_PBKDF2_ITERATIONS: u32 = 65_536;
+/// dkLen of both seeds. Not a parameter: the secret exponent is a 32 byte secp256k1 key whatever the cost.
+pub const WARP_KEY_LENGTH: usize = 32;
+// 2^18
+const WARP_SCRYPT_ITERATIONS: u32 = 262_144;
+const WARP_SCRYPT_CONCAT: u8 = 1u8;
+const WARP_SCRYPT_MEM_DIFF: u32 = 8;
+const WARP_SCRYPT_PAR_DIFF: u32 = 1;
+
+// Sizes of the scaled down runs estimate() times.
+const CALIBRATION_SCRYPT_ITERATIONS: u32 = 1_024;
+const CALIBRATION_PBKDF2_ITERATIONS: u32 = 4_096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WarpParams {
+    /// scrypt N, the CPU/memory cost. Note spec.json stores log2(N).
+    pub scrypt_iterations: u32,
+    /// scrypt r, the block size.
+    pub scrypt_mem_diff: u32,
+    /// scrypt p, the parallelism.
+    pub scrypt_par_diff: u32,
+    /// pbkdf2 c, the HMAC-SHA256 iteration count.
+    pub pbkdf2_iterations: u32,
+    /// Byte appended to passphrase and salt before scrypt.
+    pub scrypt_concat: u8,
+    /// Byte appended to passphrase and salt before pbkdf2.
+    pub pbkdf2_concat: u8
+}
+
+impl WarpParams {
+    /**
+     * The parameters of keybase.io/warp v1, as listed in the params block of spec.json.
+     */
+    pub fn keybase_v1() -> WarpParams {
+        WarpParams {
+            scrypt_iterations: WARP_SCRYPT_ITERATIONS,
+            scrypt_mem_diff: WARP_SCRYPT_MEM_DIFF,
+            scrypt_par_diff: WARP_SCRYPT_PAR_DIFF,
+            pbkdf2_iterations: WARP_PBKDF2_ITERATIONS,
+            scrypt_concat: WARP_SCRYPT_CONCAT,
+            pbkdf2_concat: WARP_PBKDF2_CONCAT
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), WarpError> {
+        validate_scrypt_params(self.scrypt_iterations, self.scrypt_mem_diff, self.scrypt_par_diff, WARP_KEY_LENGTH)?;
+        if self.pbkdf2_iterations == 0 {
+            return Err(WarpError::InvalidKdfParameters("pbkdf2 needs at least 1 iteration".to_owned()));
+        }
+        Ok(())
+    }
+
+    pub fn memory_bytes(&self) -> u64 {
+        scrypt_memory_bytes(self.scrypt_iterations, self.scrypt_mem_diff)
+    }
+
+    /**
+     * Times a scaled down scrypt and pbkdf2 on this machine and extrapolates to the real cost.
+     * Both scale linearly: scrypt in N and p, pbkdf2 in c.
+     */
+    pub fn estimate(&self) -> Result<CostEstimate, WarpError> {
+        self.validate()?;
+
+        let sample_n = cmp::min(self.scrypt_iterations, CALIBRATION_SCRYPT_ITERATIONS);
+        let start = PreciseTime::now();
+        perform_scrypt(b"warp".to_vec(), b"calibration".to_vec(), sample_n, self.scrypt_mem_diff, 1, WARP_KEY_LENGTH)?;
+        let scrypt_sample = start.to(PreciseTime::now());
+
+        let sample_c = cmp::min(self.pbkdf2_iterations, CALIBRATION_PBKDF2_ITERATIONS);
+        let start = PreciseTime::now();
+        perform_pbkdf2(b"warp".to_vec(), b"calibration".to_vec(), sample_c, WARP_KEY_LENGTH)?;
+        let pbkdf2_sample = start.to(PreciseTime::now());
+
+        Ok(CostEstimate {
+            memory_bytes: self.memory_bytes(),
+            scrypt_time: scale_duration(
+                scrypt_sample,
+                f64::from(self.scrypt_iterations) / f64::from(sample_n) * f64::from(self.scrypt_par_diff)
+            ),
+            pbkdf2_time: scale_duration(
+                pbkdf2_sample,
+                f64::from(self.pbkdf2_iterations) / f64::from(sample_c)
+            )
+        })
+    }
+}
+
+/// What a derivation with a set of WarpParams costs on the current machine.
+#[derive(Clone, Copy, Debug)]
+pub struct CostEstimate {
+    /// Bytes scrypt allocates for its V array (128 * r * N).
+    pub memory_bytes: u64,
+    pub scrypt_time: Duration,
+    pub pbkdf2_time: Duration
+}
+
+impl CostEstimate {
+    /**
+     * Scrypt and pbkdf2 run in parallel, so a derivation takes as long as the slowest of the two.
+     */
+    pub fn wall_time(&self) -> Duration {
+        cmp::max(self.scrypt_time, self.pbkdf2_time)
+    }
+}
+
+fn scale_duration(sample: Duration, factor: f64) -> Duration {
+    let micros = sample.num_microseconds().unwrap_or(i64::MAX) as f64 * factor;
+    if micros >= i64::MAX as f64 {
+        Duration::microseconds(i64::MAX)
+    } else {
+        Duration::microseconds(micros as i64)
+    }
+}
+
+impl Default for WarpParams {
+    fn default() -> WarpParams {
+        WarpParams::keybase_v1()
+    }
+}
+
+#[test]
+fn test_keybase_v1_matches_spec() {
+    // "params": { "N": 18, "p": 1, "r": 8, "dkLen": 32, "pbkdf2c": 65536 }
+    let params = WarpParams::keybase_v1();
+    assert_eq!(1 << 18, params.scrypt_iterations);
+    assert_eq!(1, params.scrypt_par_diff);
+    assert_eq!(8, params.scrypt_mem_diff);
+    assert_eq!(65_536, params.pbkdf2_iterations);
+    assert_eq!(0x1, params.scrypt_concat);
+    assert_eq!(0x2, params.pbkdf2_concat);
+    assert_eq!(params, WarpParams::default());
+}
+
+#[test]
+fn test_validate_params() {
+    assert_eq!(Ok(()), WarpParams::keybase_v1().validate());
+    assert_eq!(268_435_456, WarpParams::keybase_v1().memory_bytes());
+
+    let vectors = vec![
+        WarpParams { scrypt_iterations: 3_000, ..WarpParams::keybase_v1() },
+        WarpParams { scrypt_iterations: 1, ..WarpParams::keybase_v1() },
+        WarpParams { scrypt_mem_diff: 0, ..WarpParams::keybase_v1() },
+        WarpParams { scrypt_par_diff: 0, ..WarpParams::keybase_v1() },
+        WarpParams { pbkdf2_iterations: 0, ..WarpParams::keybase_v1() }
+    ];
+    for params in vectors {
+        println!("Testing invalid params {:?}", params);
+        assert!(params.validate().is_err());
+        assert!(params.estimate().is_err());
+    }
+}
+
+#[test]
+fn test_estimate_reduced_params() {
+    let params = WarpParams {
+        scrypt_iterations: 2_048,
+        scrypt_par_diff: 2,
+        pbkdf2_iterations: 8_192,
+        ..WarpParams::keybase_v1()
+    };
+    let estimate = params.estimate().unwrap();
+    assert_eq!(2_097_152, estimate.memory_bytes);
+    assert!(estimate.wall_time() >= estimate.scrypt_time);
+    assert!(estimate.wall_time() >= estimate.pbkdf2_time);
+    assert!(estimate.scrypt_time > Duration::zero());
+}
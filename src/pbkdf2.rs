@@ -4,14 +4,24 @@ extern crate sha2;
 extern crate hex;
 
 use self::ring::{digest, pbkdf2};
+use error::WarpError;
+use scrypt::MAX_KEY_LENGTH;
 use std::thread;
 
-pub fn perform_pbkdf2(pass_phrase: Vec<u8>, salt: Vec<u8>, iterations: u32, key_length: usize) -> Vec<u8> {
+pub fn perform_pbkdf2(pass_phrase: Vec<u8>, salt: Vec<u8>, iterations: u32, key_length: usize) -> Result<Vec<u8>, WarpError> {
+    if iterations == 0 {
+        return Err(WarpError::InvalidKdfParameters("pbkdf2 needs at least 1 iteration".to_owned()));
+    }
+    // dkLen <= (2^32 - 1) * hLen
+    if key_length == 0 || key_length as u64 > MAX_KEY_LENGTH {
+        return Err(WarpError::InvalidKdfParameters(format!("key length {} is out of range", key_length)));
+    }
+
     thread::spawn(move || {
         let mut to_store = vec![0u8; key_length];
         pbkdf2::derive(&digest::SHA256, iterations, &salt, &pass_phrase, &mut to_store);
         to_store
-    }).join().unwrap()
+    }).join().map_err(WarpError::from)
 }
 
 #[test]
@@ -53,6 +63,12 @@ fn test_pbkdf2_vectors() {
 
     for tuple in vectors {
         println!("Testing phrase [{}] with salt [{}] and {} iterations.", tuple.1, tuple.2, tuple.3);
-        assert_eq!(tuple.0, hex::encode(perform_pbkdf2(tuple.1.as_bytes().to_vec(), tuple.2.as_bytes().to_vec(), tuple.3, tuple.4)));
+        assert_eq!(tuple.0, hex::encode(perform_pbkdf2(tuple.1.as_bytes().to_vec(), tuple.2.as_bytes().to_vec(), tuple.3, tuple.4).unwrap()));
     }
 }
+
+#[test]
+fn test_invalid_pbkdf2_parameters() {
+    assert!(perform_pbkdf2(b"password".to_vec(), b"salt".to_vec(), 0, 32).is_err());
+    assert!(perform_pbkdf2(b"password".to_vec(), b"salt".to_vec(), 1, 0).is_err());
+}
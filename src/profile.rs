@@ -0,0 +1,333 @@
+// Candidates made of what the family knows about the owner, as CUPP does: names, pets, places and
+// favourite phrases combined with dates, in a few case variants and with the given separators.
+//
+// A profile has one detail per line, # comments and blank lines are skipped:
+//      name: Lucy Smith        a key is only a label, any word will do
+//      pet: Rex
+//      birthday: 1987-03-07    a YYYY-MM-DD date or a YYYY year, whatever its key
+//      carpe diem              a line without a key is a detail too
+// A detail of several words is tried whole, its words joined like any other, and word by word.
+//
+// Candidates come in rounds, likeliest first: a detail alone, a detail with a date after or before
+// it, a date alone, two details, two details and a date. In every round the separator turns slowest
+// and the last part fastest. A candidate that more than one round makes is tried once, the first time.
+
+use combinator::Separator;
+use error::WarpError;
+#[cfg(test)]
+use keyspace;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Part {
+    Detail,
+    Date
+}
+
+const ROUNDS: [&[Part]; 6] = [
+    &[Part::Detail],
+    &[Part::Detail, Part::Date],
+    &[Part::Date, Part::Detail],
+    &[Part::Date],
+    &[Part::Detail, Part::Detail],
+    &[Part::Detail, Part::Detail, Part::Date]
+];
+
+/// What is known about the owner, built detail by detail.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Names, places and phrases split into words, in order.
+    details: Vec<Vec<String>>,
+    /// Every way of writing each date, in order.
+    dates: Vec<Vec<u8>>
+}
+
+impl Profile {
+    pub fn new() -> Profile {
+        Profile::default()
+    }
+
+    /**
+     * A name, place or phrase. One of several words also adds each of its words.
+     */
+    pub fn add_detail(&mut self, detail: &str) -> Result<(), WarpError> {
+        let words: Vec<String> = detail.split_whitespace().map(str::to_owned).collect();
+        if words.is_empty() {
+            return Err(WarpError::InvalidPattern("a profile detail can not be empty".to_owned()));
+        }
+        let single: Vec<Vec<String>> = if words.len() > 1 {
+            words.iter().map(|word| vec![word.clone()]).collect()
+        } else {
+            vec![]
+        };
+        for detail in Some(words).into_iter().chain(single) {
+            if !self.details.contains(&detail) {
+                self.details.push(detail);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * A YYYY-MM-DD date or a YYYY year.
+     */
+    pub fn add_date(&mut self, date: &str) -> Result<(), WarpError> {
+        for form in date_forms(date)?.into_iter().map(String::into_bytes) {
+            if !self.dates.contains(&form) {
+                self.dates.push(form);
+            }
+        }
+        Ok(())
+    }
+
+    /**
+     * One line of a profile file, [key:] value. A value that is a date is a date, whatever its key.
+     */
+    pub fn add_line(&mut self, line: &str) -> Result<(), WarpError> {
+        let line = line.trim();
+        let value = match line.find(':') {
+            Some(colon) if colon > 0 && line[..colon].bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_') => line[colon + 1..].trim(),
+            _ => line
+        };
+        match date_forms(value) {
+            Ok(_) => self.add_date(value),
+            Err(_) => self.add_detail(value)
+        }
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<Profile, WarpError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut profile = Profile::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            profile.add_line(&line).map_err(|error| WarpError::InvalidPattern(format!("line {}: {}", index + 1, error)))?;
+        }
+        Ok(profile)
+    }
+
+    /**
+     * Every candidate of the profile, each detail as written, lowercase, capitalized and uppercase.
+     */
+    pub fn candidates(&self, separators: Vec<Separator>) -> Result<ProfileCandidates, WarpError> {
+        if self.details.is_empty() {
+            return Err(WarpError::InvalidPattern("a profile needs at least one name, place or phrase".to_owned()));
+        }
+        if separators.is_empty() {
+            return Err(WarpError::InvalidPattern("a profile needs at least one separator".to_owned()));
+        }
+        let mut variants: Vec<(usize, Vec<Vec<u8>>)> = vec![];
+        for (index, detail) in self.details.iter().enumerate() {
+            let cases: [fn(&str) -> String; 4] = [str::to_owned, str::to_lowercase, capitalized, str::to_uppercase];
+            for case in &cases {
+                let words: Vec<Vec<u8>> = detail.iter().map(|word| case(word).into_bytes()).collect();
+                if !variants.iter().any(|variant| variant.0 == index && variant.1 == words) {
+                    variants.push((index, words));
+                }
+            }
+        }
+        Ok(ProfileCandidates { variants, dates: self.dates.clone(), separators, round: 0, digits: None, seen: HashSet::new() })
+    }
+}
+
+/**
+ * Every usual way of writing a YYYY-MM-DD date: 1987, 87, 0703, 0307, 07031987, 03071987, 19870307,
+ * 070387 and 030787. A YYYY year is written 1987 and 87.
+ */
+fn date_forms(date: &str) -> Result<Vec<String>, WarpError> {
+    let invalid = || WarpError::InvalidPattern(format!("{} is not a YYYY-MM-DD date or a YYYY year", date));
+    let fields: Vec<&str> = date.trim().split('-').collect();
+    if fields.iter().any(|field| field.is_empty() || !field.bytes().all(|byte| byte.is_ascii_digit())) || fields[0].len() != 4 {
+        return Err(invalid());
+    }
+    let (year, yy) = (fields[0], &fields[0][2..]);
+    match fields.len() {
+        1 => Ok(vec![year.to_owned(), yy.to_owned()]),
+        3 => {
+            let month: u32 = fields[1].parse().map_err(|_| invalid())?;
+            let day: u32 = fields[2].parse().map_err(|_| invalid())?;
+            if month == 0 || month > 12 || day == 0 || day > 31 {
+                return Err(invalid());
+            }
+            let (mm, dd) = (format!("{:02}", month), format!("{:02}", day));
+            Ok(vec![
+                year.to_owned(), yy.to_owned(), format!("{}{}", dd, mm), format!("{}{}", mm, dd),
+                format!("{}{}{}", dd, mm, year), format!("{}{}{}", mm, dd, year), format!("{}{}{}", year, mm, dd),
+                format!("{}{}{}", dd, mm, yy), format!("{}{}{}", mm, dd, yy)
+            ])
+        },
+        _ => Err(invalid())
+    }
+}
+
+fn capitalized(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(char::to_lowercase)).collect(),
+        None => String::new()
+    }
+}
+
+/// Streams the candidates of a profile, round by round.
+pub struct ProfileCandidates {
+    /// (detail, its words in one case), every case of every detail.
+    variants: Vec<(usize, Vec<Vec<u8>>)>,
+    dates: Vec<Vec<u8>>,
+    separators: Vec<Separator>,
+    round: usize,
+    /// The separator, then one digit per part of the round. None between rounds.
+    digits: Option<Vec<usize>>,
+    /// Everything tried so far, so a later round does not repeat it.
+    seen: HashSet<Vec<u8>>
+}
+
+impl ProfileCandidates {
+    fn radix(&self, position: usize) -> usize {
+        match position {
+            0 => self.separators.len(),
+            _ => match ROUNDS[self.round][position - 1] {
+                Part::Detail => self.variants.len(),
+                Part::Date => self.dates.len()
+            }
+        }
+    }
+
+    /**
+     * The candidate of the current digits, None if it uses one detail twice.
+     */
+    fn render(&self, digits: &[usize]) -> Option<Vec<u8>> {
+        let mut words: Vec<&[u8]> = vec![];
+        let mut details: Vec<usize> = vec![];
+        for (part, digit) in ROUNDS[self.round].iter().zip(&digits[1..]) {
+            match *part {
+                Part::Detail => {
+                    let (detail, ref variant) = self.variants[*digit];
+                    if details.contains(&detail) {
+                        return None;
+                    }
+                    details.push(detail);
+                    words.extend(variant.iter().map(|word| &word[..]));
+                },
+                Part::Date => words.push(&self.dates[*digit])
+            }
+        }
+        Some(self.separators[digits[0]].join(&words))
+    }
+}
+
+impl Iterator for ProfileCandidates {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        while self.round < ROUNDS.len() {
+            let mut digits = match self.digits.take() {
+                Some(digits) => digits,
+                None => {
+                    let positions = ROUNDS[self.round].len() + 1;
+                    if (0..positions).any(|position| self.radix(position) == 0) {
+                        self.round += 1;
+                        continue;
+                    }
+                    vec![0; positions]
+                }
+            };
+            let candidate = self.render(&digits);
+
+            let mut position = digits.len();
+            loop {
+                if position == 0 {
+                    self.round += 1;
+                    break;
+                }
+                position -= 1;
+                digits[position] += 1;
+                if digits[position] < self.radix(position) {
+                    self.digits = Some(digits);
+                    break;
+                }
+                digits[position] = 0;
+            }
+
+            if let Some(candidate) = candidate {
+                if self.seen.insert(candidate.clone()) {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+fn test_profile(lines: &[&str]) -> Profile {
+    let mut profile = Profile::new();
+    for line in lines {
+        profile.add_line(line).unwrap();
+    }
+    profile
+}
+
+#[test]
+fn test_profile_dates() {
+    let vectors: Vec<(&str, Vec<&str>)> = vec![
+        // Date, ways of writing it
+        ("1987-03-07", vec!["1987", "87", "0703", "0307", "07031987", "03071987", "19870307", "070387", "030787"]),
+        ("2001-12-1", vec!["2001", "01", "0112", "1201", "01122001", "12012001", "20011201", "011201", "120101"]),
+        ("1999", vec!["1999", "99"])
+    ];
+    for tuple in vectors {
+        println!("Testing date [{}]", tuple.0);
+        let mut profile = Profile::new();
+        profile.add_date(tuple.0).unwrap();
+        assert_eq!(tuple.1.iter().map(|form| form.as_bytes().to_vec()).collect::<Vec<Vec<u8>>>(), profile.dates);
+    }
+    for date in &["87", "1987-13-01", "1987-03-00", "1987-03", "1987/03/07", "19a7", ""] {
+        println!("Testing invalid date [{}]", date);
+        assert!(Profile::new().add_date(date).is_err());
+    }
+}
+
+#[test]
+fn test_profile_lines() {
+    let profile = test_profile(&["name: Lucy Smith", "pet:Rex", "birthday: 1999", "to be: or not", "  42  ", "rex"]);
+    assert_eq!(vec![
+        vec!["Lucy".to_owned(), "Smith".to_owned()], vec!["Lucy".to_owned()], vec!["Smith".to_owned()], vec!["Rex".to_owned()],
+        vec!["to".to_owned(), "be:".to_owned(), "or".to_owned(), "not".to_owned()], vec!["to".to_owned()], vec!["be:".to_owned()],
+        vec!["or".to_owned()], vec!["not".to_owned()], vec!["42".to_owned()], vec!["rex".to_owned()]
+    ], profile.details);
+    assert_eq!(vec![b"1999".to_vec(), b"99".to_vec()], profile.dates);
+    assert!(Profile::new().add_line("name:").is_err());
+    assert!(Profile::new().candidates(vec![Separator::None]).is_err());
+    assert!(test_profile(&["Rex"]).candidates(vec![]).is_err());
+}
+
+#[test]
+fn test_profile_candidates() {
+    let profile = test_profile(&["pet: Rex", "city: Oslo", "birthday: 1999"]);
+    let candidates = keyspace::as_strings(profile.candidates(vec![Separator::None, Separator::Dash]).unwrap());
+    assert_eq!(vec![
+        "Rex", "rex", "REX", "Oslo", "oslo", "OSLO",
+        "Rex1999", "Rex99", "rex1999", "rex99", "REX1999", "REX99"
+    ], candidates[..12].to_vec());
+    for candidate in &["Rex-1999", "99oslo", "99-OSLO", "1999", "99", "RexOslo", "oslo-rex", "REXoslo99", "Oslo-rex-1999"] {
+        assert!(candidates.contains(&candidate.to_string()));
+    }
+    assert!(!candidates.contains(&"RexRex".to_owned()));
+    assert!(!candidates.contains(&"rex-REX".to_owned()));
+    // A detail alone is the same with either separator.
+    assert_eq!(1, candidates.iter().filter(|candidate| *candidate == "Rex").count());
+
+    keyspace::assert_distinct(&candidates);
+    // Details 6, with a date after or before 2 * 2 * 6 * 2, dates 2, two details 2 * 6 * 3,
+    // and two details and a date 2 * 6 * 3 * 2.
+    assert_eq!(6 + 48 + 2 + 36 + 72, candidates.len());
+
+    let candidates: Vec<Vec<u8>> = test_profile(&["fav: carpe diem"]).candidates(vec![Separator::Capitalized]).unwrap().collect();
+    assert_eq!(b"CarpeDiem".to_vec(), candidates[0]);
+    assert!(candidates.contains(&b"DiemCarpe".to_vec()));
+}
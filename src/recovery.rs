@@ -0,0 +1,472 @@
+// Recovers a passphrase from what its owner still remembers: "the six words, but not their order",
+// "five of the words, the last one is from the EFF list". Every remembered word, or unknown word with
+// its candidates, is a slot. A slot can be pinned to a position and one slot can be required to come
+// before another. Every order of the slots that satisfies the constraints is counted up front, so
+// the phrases can be numbered: the separator turns fastest, then the candidates of the last
+// position, then the order of the slots.
+//
+// Slots with the same candidates, in any order, are tried in one order only. Slots whose candidates
+// only overlap are not: "x" and "x|y", or a remembered word that is also in a ?wordlist, make some
+// phrases more than once and the keyspace counts every one of them.
+//
+// A recovery spec has one slot per line, # comments and blank lines are skipped:
+//      correct             a remembered word
+//      staple|stable       one of these words
+//      ?eff_large.txt      any word of this wordlist
+//      3:battery           pinned, the third word of the phrase
+//      correct < battery   correct comes somewhere before battery, slots are named as written
+
+use combinator::Separator;
+use error::WarpError;
+use keyspace::{self, Keyspace};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use wordlist::Wordlist;
+
+/// Orders of more slots than this are not worth enumerating, and the counting table grows with 2^slots.
+pub const MAX_SLOTS: usize = 16;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Slot {
+    /// The slot as written in the spec, how constraints refer to it.
+    label: String,
+    choices: Vec<Vec<u8>>,
+    /// Pinned position, 0 based.
+    position: Option<usize>
+}
+
+/// What is remembered of a phrase, built slot by slot.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RecoverySpec {
+    slots: Vec<Slot>,
+    /// (a, b): slot a comes before slot b.
+    before: Vec<(usize, usize)>
+}
+
+impl RecoverySpec {
+    pub fn new() -> RecoverySpec {
+        RecoverySpec::default()
+    }
+
+    /**
+     * A remembered word. Returns the slot, for pin and before.
+     */
+    pub fn add_word(&mut self, word: &str) -> usize {
+        self.add_choices(word, vec![word.as_bytes().to_vec()])
+    }
+
+    /**
+     * A word that is one of `choices`, duplicates are tried once.
+     */
+    pub fn add_choices(&mut self, label: &str, choices: Vec<Vec<u8>>) -> usize {
+        let mut distinct: Vec<Vec<u8>> = vec![];
+        for choice in choices {
+            if !distinct.contains(&choice) {
+                distinct.push(choice);
+            }
+        }
+        self.slots.push(Slot { label: label.to_owned(), choices: distinct, position: None });
+        self.slots.len() - 1
+    }
+
+    /**
+     * Pins a slot to a position of the phrase, 0 based.
+     */
+    pub fn pin(&mut self, slot: usize, position: usize) {
+        self.slots[slot].position = Some(position);
+    }
+
+    pub fn before(&mut self, first: usize, then: usize) {
+        self.before.push((first, then));
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /**
+     * Adds one line of a spec, see the top of this file. Constraints can only name slots added before them.
+     */
+    pub fn add_line(&mut self, line: &str) -> Result<(), WarpError> {
+        let line = line.trim();
+        if let Some(split) = line.find(" < ") {
+            let first = self.find_slots(line[..split].trim())?;
+            let then = self.find_slots(line[split + 3..].trim())?;
+            for a in &first {
+                for b in &then {
+                    self.before(*a, *b);
+                }
+            }
+            return Ok(());
+        }
+
+        let (position, text) = match line.find(':') {
+            Some(colon) if colon > 0 && line[..colon].bytes().all(|byte| byte.is_ascii_digit()) => {
+                let position: usize = line[..colon].parse()
+                    .map_err(|_| WarpError::InvalidPattern(format!("invalid position in {}", line)))?;
+                if position == 0 {
+                    return Err(WarpError::InvalidPattern("positions start at 1".to_owned()));
+                }
+                (Some(position - 1), &line[colon + 1..])
+            },
+            _ => (None, line)
+        };
+        if text.is_empty() {
+            return Err(WarpError::InvalidPattern(format!("{} has no word", line)));
+        }
+        let choices: Vec<Vec<u8>> = if let Some(path) = text.strip_prefix('?') {
+            Wordlist::open(vec![path]).collect::<Result<Vec<Vec<u8>>, WarpError>>()?
+        } else {
+            text.split('|').filter(|choice| !choice.is_empty()).map(|choice| choice.as_bytes().to_vec()).collect()
+        };
+        if choices.is_empty() {
+            return Err(WarpError::InvalidPattern(format!("{} has no candidates", text)));
+        }
+        let slot = self.add_choices(text, choices);
+        if let Some(position) = position {
+            self.pin(slot, position);
+        }
+        Ok(())
+    }
+
+    fn find_slots(&self, label: &str) -> Result<Vec<usize>, WarpError> {
+        let slots: Vec<usize> = (0..self.slots.len()).filter(|slot| self.slots[*slot].label == label).collect();
+        if slots.is_empty() {
+            return Err(WarpError::InvalidPattern(format!("no slot {} above the constraint", label)));
+        }
+        Ok(slots)
+    }
+
+    pub fn load_file<P: AsRef<Path>>(path: P) -> Result<RecoverySpec, WarpError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut spec = RecoverySpec::new();
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            spec.add_line(&line).map_err(|error| WarpError::InvalidPattern(format!("line {}: {}", index + 1, error)))?;
+        }
+        Ok(spec)
+    }
+
+    /**
+     * Slots with the same set of candidates, pin and constraints give the same phrases in either order,
+     * so only the order they were added in is kept. Two "the" slots would otherwise try every phrase twice.
+     */
+    fn interchangeable(&self, a: usize, b: usize) -> bool {
+        let related = |x: usize, y: usize| self.before.contains(&(x, y)) || self.before.contains(&(y, x));
+        let candidates = |slot: usize| {
+            let mut choices: Vec<&Vec<u8>> = self.slots[slot].choices.iter().collect();
+            choices.sort();
+            choices
+        };
+        candidates(a) == candidates(b)
+            && self.slots[a].position == self.slots[b].position
+            && !related(a, b)
+            && (0..self.slots.len()).filter(|other| *other != a && *other != b).all(|other| {
+                self.before.contains(&(a, other)) == self.before.contains(&(b, other))
+                    && self.before.contains(&(other, a)) == self.before.contains(&(other, b))
+            })
+    }
+}
+
+/// A spec ready to enumerate, with the number of ways every partial order can be completed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    slots: Vec<Slot>,
+    /// Bit mask of the slots that have to come before each slot.
+    predecessors: Vec<usize>,
+    /// completions[placed]: orders of the remaining slots once the slots of `placed` fill the first positions.
+    completions: Vec<u128>,
+    separators: Vec<Separator>,
+    /// Phrases per order of the slots, without separators.
+    per_order: u128
+}
+
+impl Recovery {
+    pub fn new(spec: RecoverySpec) -> Result<Recovery, WarpError> {
+        let slot_count = spec.slots.len();
+        if slot_count == 0 {
+            return Err(WarpError::InvalidPattern("a recovery needs at least one word".to_owned()));
+        }
+        if slot_count > MAX_SLOTS {
+            return Err(WarpError::InvalidPattern(format!("at most {} words can be recovered", MAX_SLOTS)));
+        }
+        if let Some(slot) = spec.slots.iter().find(|slot| slot.position.is_some_and(|position| position >= slot_count)) {
+            return Err(WarpError::InvalidPattern(format!("{} is pinned past the end of a {} word phrase", slot.label, slot_count)));
+        }
+
+        let mut before = spec.before.clone();
+        for a in 0..slot_count {
+            for b in a + 1..slot_count {
+                if spec.interchangeable(a, b) {
+                    before.push((a, b));
+                }
+            }
+        }
+        let mut predecessors = vec![0; slot_count];
+        for (first, then) in before {
+            if first == then {
+                return Err(WarpError::InvalidPattern(format!("{} can not come before itself", spec.slots[first].label)));
+            }
+            predecessors[then] |= 1 << first;
+        }
+
+        let per_order = spec.slots.iter()
+            .try_fold(1u128, |total, slot| total.checked_mul(slot.choices.len() as u128))
+            .ok_or_else(keyspace::overflow)?;
+        let mut recovery = Recovery {
+            slots: spec.slots,
+            predecessors,
+            completions: vec![0; 1 << slot_count],
+            separators: vec![Separator::Space],
+            per_order
+        };
+        let full = (1 << slot_count) - 1;
+        recovery.completions[full] = 1;
+        for placed in (0..full).rev() {
+            recovery.completions[placed] = (0..slot_count)
+                .filter(|slot| recovery.can_place(placed, *slot))
+                .map(|slot| recovery.completions[placed | 1 << slot])
+                .sum();
+        }
+        if recovery.orders() == 0 {
+            return Err(WarpError::InvalidPattern("no order of the words satisfies the constraints".to_owned()));
+        }
+        Recovery::checked(recovery)
+    }
+
+    /**
+     * Every phrase is tried once with each separator, in the order given.
+     */
+    pub fn with_separators(self, separators: Vec<Separator>) -> Result<Recovery, WarpError> {
+        if separators.is_empty() {
+            return Err(WarpError::InvalidPattern("at least one separator is needed".to_owned()));
+        }
+        Recovery::checked(Recovery { separators, ..self })
+    }
+
+    fn checked(recovery: Recovery) -> Result<Recovery, WarpError> {
+        recovery.orders().checked_mul(recovery.per_order)
+            .and_then(|total| total.checked_mul(recovery.separators.len() as u128))
+            .ok_or_else(keyspace::overflow)?;
+        Ok(recovery)
+    }
+
+    /**
+     * Whether `slot` can take the next position once the slots of `placed` fill the ones before it.
+     */
+    fn can_place(&self, placed: usize, slot: usize) -> bool {
+        let position = placed.count_ones() as usize;
+        placed & 1 << slot == 0
+            && self.slots[slot].position.is_none_or(|pinned| pinned == position)
+            && self.predecessors[slot] & placed == self.predecessors[slot]
+    }
+
+    /**
+     * The number of orders of the slots that satisfy every constraint.
+     */
+    pub fn orders(&self) -> u128 {
+        self.completions[0]
+    }
+
+    /**
+     * Order number `index` as the slot at every position, the slots are tried in the order they were added.
+     */
+    fn order(&self, mut index: u128) -> Vec<usize> {
+        let mut placed = 0;
+        let mut order = Vec::with_capacity(self.slots.len());
+        for _ in 0..self.slots.len() {
+            for slot in 0..self.slots.len() {
+                if !self.can_place(placed, slot) {
+                    continue;
+                }
+                let completions = self.completions[placed | 1 << slot];
+                if index < completions {
+                    order.push(slot);
+                    placed |= 1 << slot;
+                    break;
+                }
+                index -= completions;
+            }
+        }
+        order
+    }
+
+    fn render(&self, order: &[usize], mut index: u128) -> Vec<u8> {
+        let separators = self.separators.len() as u128;
+        let separator = &self.separators[(index % separators) as usize];
+        index /= separators;
+        let mut words: Vec<&[u8]> = vec![&[]; order.len()];
+        for position in (0..order.len()).rev() {
+            let choices = &self.slots[order[position]].choices;
+            words[position] = &choices[(index % choices.len() as u128) as usize];
+            index /= choices.len() as u128;
+        }
+        separator.join(&words)
+    }
+
+    fn phrases_per_order(&self) -> u128 {
+        self.per_order * self.separators.len() as u128
+    }
+}
+
+impl Keyspace for Recovery {
+    /**
+     * The number of phrases: orders, times the candidates of every slot, times the separators. Slots
+     * with overlapping candidates make some phrases more than once, each is counted.
+     */
+    fn keyspace(&self) -> u128 {
+        self.orders() * self.per_order * self.separators.len() as u128
+    }
+
+    fn candidate(&self, index: u128) -> Option<Vec<u8>> {
+        if index >= self.keyspace() {
+            return None;
+        }
+        let per_order = self.phrases_per_order();
+        Some(self.render(&self.order(index / per_order), index % per_order))
+    }
+
+    fn iter_from(&self, start: u128) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        Box::new(RecoveryIter { recovery: self, index: start, order: None })
+    }
+}
+
+/// Walks a recovery's keyspace, working out each order of the slots once.
+struct RecoveryIter<'a> {
+    recovery: &'a Recovery,
+    index: u128,
+    /// The current order and its number.
+    order: Option<(u128, Vec<usize>)>
+}
+
+impl<'a> Iterator for RecoveryIter<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let recovery = self.recovery;
+        if self.index >= recovery.keyspace() {
+            return None;
+        }
+        let per_order = recovery.phrases_per_order();
+        let order_index = self.index / per_order;
+        if self.order.as_ref().map(|order| order.0) != Some(order_index) {
+            self.order = Some((order_index, recovery.order(order_index)));
+        }
+        let order = &self.order.as_ref().expect("set above").1;
+        let candidate = recovery.render(order, self.index % per_order);
+        self.index += 1;
+        Some(candidate)
+    }
+}
+
+#[cfg(test)]
+fn test_spec(lines: &[&str]) -> RecoverySpec {
+    let mut spec = RecoverySpec::new();
+    for line in lines {
+        spec.add_line(line).unwrap();
+    }
+    spec
+}
+
+#[test]
+fn test_recover_order() {
+    let recovery = Recovery::new(test_spec(&["correct", "horse", "battery"])).unwrap();
+    assert_eq!(6, recovery.orders());
+    assert_eq!(6, recovery.keyspace());
+    let phrases = keyspace::as_strings(keyspace::assert_numbering(&recovery));
+    assert_eq!(vec![
+        "correct horse battery", "correct battery horse", "horse correct battery",
+        "horse battery correct", "battery correct horse", "battery horse correct"
+    ], phrases);
+    assert_eq!(phrases[4..].to_vec(), keyspace::as_strings(recovery.iter_from(4)));
+}
+
+#[test]
+fn test_recover_constraints() {
+    let vectors: Vec<(Vec<&str>, u128)> = vec![
+        // Spec, orders
+        (vec!["a", "b", "c", "d"], 24),
+        (vec!["a", "b", "c", "1:d"], 6),
+        (vec!["a", "b", "c", "1:d", "a < b"], 3),
+        (vec!["a", "b", "c", "d", "a < b", "b < c", "c < d"], 1),
+        (vec!["a", "b", "2:c", "4:d"], 2),
+        (vec!["the", "the", "cat"], 3),
+        (vec!["the", "the", "the", "cat", "sat"], 20),
+        (vec!["x|y", "x|y", "z"], 3),
+        (vec!["x|y", "y|x", "z"], 3),
+        (vec!["a|b|c", "c|a|b", "b|c|a"], 1),
+        (vec!["the", "the", "cat", "the < cat"], 1)
+    ];
+    for tuple in vectors {
+        println!("Testing recovery {:?}", tuple.0);
+        let recovery = Recovery::new(test_spec(&tuple.0)).unwrap();
+        assert_eq!(tuple.1, recovery.orders());
+        keyspace::assert_distinct(&keyspace::assert_numbering(&recovery));
+    }
+
+    let recovery = Recovery::new(test_spec(&["a", "b", "c", "1:d", "a < b"])).unwrap();
+    assert_eq!(vec!["d a b c", "d a c b", "d c a b"], keyspace::as_strings(recovery.iter()));
+}
+
+#[test]
+fn test_recover_unknown_words() {
+    let recovery = Recovery::new(test_spec(&["3:staple|stable", "correct", "horse"])).unwrap()
+        .with_separators(vec![Separator::Space, Separator::Capitalized]).unwrap();
+    assert_eq!(2, recovery.orders());
+    assert_eq!(2 * 2 * 2, recovery.keyspace());
+    assert_eq!(vec![
+        "correct horse staple", "CorrectHorseStaple", "correct horse stable", "CorrectHorseStable",
+        "horse correct staple", "HorseCorrectStaple", "horse correct stable", "HorseCorrectStable"
+    ], keyspace::as_strings(recovery.iter()));
+    assert_eq!(Some(b"horse correct staple".to_vec()), recovery.candidate(4));
+}
+
+#[test]
+fn test_recover_spec_file() {
+    use std::env;
+    use std::fs;
+
+    let words = env::temp_dir().join("rust-warpwallet-test-recovery-words.txt");
+    let spec = env::temp_dir().join("rust-warpwallet-test-recovery.txt");
+    fs::write(&words, "apple\nbanana\ncherry\n").unwrap();
+    fs::write(&spec, format!("# the fruit came last\nred\n\nblue\n3:?{}\nred < blue\n", words.display())).unwrap();
+    let recovery = Recovery::new(RecoverySpec::load_file(&spec).unwrap()).unwrap();
+    assert_eq!(vec!["red blue apple", "red blue banana", "red blue cherry"], keyspace::as_strings(recovery.iter()));
+
+    fs::write(&spec, "red\nred < green\n").unwrap();
+    match RecoverySpec::load_file(&spec) {
+        Err(WarpError::InvalidPattern(message)) => assert!(message.starts_with("line 2:")),
+        other => panic!("expected an invalid pattern, got {:?}", other)
+    }
+    fs::remove_file(&words).unwrap();
+    fs::remove_file(&spec).unwrap();
+}
+
+#[test]
+fn test_invalid_recoveries() {
+    let vectors: Vec<Vec<&str>> = vec![
+        vec![],
+        vec!["a", "b", "4:c"],
+        vec!["1:a", "1:b"],
+        vec!["a", "b", "a < b", "b < a"],
+        vec!["a", "a < a"],
+        vec!["a"; MAX_SLOTS + 1]
+    ];
+    for vector in vectors {
+        println!("Testing invalid recovery {:?}", vector);
+        assert!(Recovery::new(test_spec(&vector)).is_err());
+    }
+    let mut spec = RecoverySpec::new();
+    assert!(spec.add_line("0:a").is_err());
+    assert!(spec.add_line("2:").is_err());
+    assert!(spec.add_line("|").is_err());
+    assert!(spec.add_line("a < b").is_err());
+}
@@ -0,0 +1,508 @@
+// Hashcat/John style word mangling rules, applied to every wordlist entry before derivation.
+// A rule is a line of functions applied left to right, spaces between them are ignored. Positions
+// N and M are 0-9 then A-Z for 10-35, X and Y are single bytes. Case functions only touch ASCII.
+//
+//      :     nothing              l u c C t   lower, upper, capitalize, invert capitalize, toggle case
+//      TN    toggle case at N     r d f       reverse, duplicate, append reversed
+//      pN    append N copies      { }         rotate left, rotate right
+//      $X ^X append, prepend X    [ ]         delete first, delete last
+//      DN    delete at N          'N          truncate to N
+//      xNM   keep M from N        ONM         delete M from N
+//      iNX   insert X at N        oNX         overwrite at N with X
+//      sXY   replace X with Y     @X          purge X
+//      zN ZN repeat first, last N times       q   double every character
+// Rejection functions drop the candidate:
+//      <N    unless shorter than N            >N  unless longer than N     _N  unless exactly N long
+//      !X    if it contains X     /X          unless it contains X
+//      (X    unless it starts with X          )X  unless it ends with X
+// Like hashcat, a candidate may be at most 256 bytes long. A function that would grow it past that
+// drops it instead, so a rule like pZpZpZpZ can not blow a word up beyond memory.
+
+use error::WarpError;
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+const MAX_WORD_LENGTH: usize = 256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Function {
+    Nothing,
+    Lower,
+    Upper,
+    Capitalize,
+    InvertCapitalize,
+    ToggleAll,
+    Toggle(usize),
+    Reverse,
+    Duplicate,
+    DuplicateTimes(usize),
+    Reflect,
+    RotateLeft,
+    RotateRight,
+    Append(u8),
+    Prepend(u8),
+    DeleteFirst,
+    DeleteLast,
+    DeleteAt(usize),
+    Truncate(usize),
+    Extract(usize, usize),
+    Omit(usize, usize),
+    Insert(usize, u8),
+    Overwrite(usize, u8),
+    Replace(u8, u8),
+    Purge(u8),
+    RepeatFirst(usize),
+    RepeatLast(usize),
+    DoubleEach,
+    RejectUnlessShorter(usize),
+    RejectUnlessLonger(usize),
+    RejectUnlessLength(usize),
+    RejectContains(u8),
+    RejectUnlessContains(u8),
+    RejectUnlessStarts(u8),
+    RejectUnlessEnds(u8)
+}
+
+fn position(byte: u8) -> Option<usize> {
+    match byte {
+        b'0'..=b'9' => Some((byte - b'0') as usize),
+        b'A'..=b'Z' => Some((byte - b'A') as usize + 10),
+        _ => None
+    }
+}
+
+fn toggle_case(byte: u8) -> u8 {
+    if byte.is_ascii_lowercase() {
+        byte.to_ascii_uppercase()
+    } else {
+        byte.to_ascii_lowercase()
+    }
+}
+
+impl Function {
+    /**
+     * The transformed word, None when a rejection function drops it.
+     */
+    fn apply(self, mut word: Vec<u8>) -> Option<Vec<u8>> {
+        let length = match self {
+            Function::Duplicate | Function::Reflect | Function::DoubleEach => word.len().checked_mul(2),
+            Function::DuplicateTimes(times) => word.len().checked_mul(times + 1),
+            Function::RepeatFirst(times) | Function::RepeatLast(times) => word.len().checked_add(times),
+            Function::Append(_) | Function::Prepend(_) | Function::Insert(..) => word.len().checked_add(1),
+            _ => Some(word.len())
+        };
+        match length {
+            Some(length) if length <= MAX_WORD_LENGTH => (),
+            _ => return None
+        }
+        match self {
+            Function::Nothing => (),
+            Function::Lower => word.make_ascii_lowercase(),
+            Function::Upper => word.make_ascii_uppercase(),
+            Function::Capitalize => {
+                word.make_ascii_lowercase();
+                if let Some(first) = word.first_mut() {
+                    first.make_ascii_uppercase();
+                }
+            },
+            Function::InvertCapitalize => {
+                word.make_ascii_uppercase();
+                if let Some(first) = word.first_mut() {
+                    first.make_ascii_lowercase();
+                }
+            },
+            Function::ToggleAll => word.iter_mut().for_each(|byte| *byte = toggle_case(*byte)),
+            Function::Toggle(at) => if let Some(byte) = word.get_mut(at) {
+                *byte = toggle_case(*byte);
+            },
+            Function::Reverse => word.reverse(),
+            Function::Duplicate => word = word.repeat(2),
+            Function::DuplicateTimes(times) => word = word.repeat(times + 1),
+            Function::Reflect => {
+                let reversed: Vec<u8> = word.iter().rev().cloned().collect();
+                word.extend(reversed);
+            },
+            Function::RotateLeft => if !word.is_empty() {
+                word.rotate_left(1);
+            },
+            Function::RotateRight => if !word.is_empty() {
+                word.rotate_right(1);
+            },
+            Function::Append(byte) => word.push(byte),
+            Function::Prepend(byte) => word.insert(0, byte),
+            Function::DeleteFirst => if !word.is_empty() {
+                word.remove(0);
+            },
+            Function::DeleteLast => {
+                word.pop();
+            },
+            Function::DeleteAt(at) => if at < word.len() {
+                word.remove(at);
+            },
+            Function::Truncate(length) => word.truncate(length),
+            Function::Extract(at, length) => {
+                if at >= word.len() {
+                    return Some(vec![]);
+                }
+                let end = (at + length).min(word.len());
+                word = word[at..end].to_vec();
+            },
+            Function::Omit(at, length) => if at < word.len() {
+                let end = (at + length).min(word.len());
+                word.drain(at..end);
+            },
+            Function::Insert(at, byte) => if at <= word.len() {
+                word.insert(at, byte);
+            },
+            Function::Overwrite(at, byte) => if let Some(existing) = word.get_mut(at) {
+                *existing = byte;
+            },
+            Function::Replace(from, to) => word.iter_mut().filter(|byte| **byte == from).for_each(|byte| *byte = to),
+            Function::Purge(byte) => word.retain(|existing| *existing != byte),
+            Function::RepeatFirst(times) => if let Some(&first) = word.first() {
+                for _ in 0..times {
+                    word.insert(0, first);
+                }
+            },
+            Function::RepeatLast(times) => if let Some(&last) = word.last() {
+                word.extend(vec![last; times]);
+            },
+            Function::DoubleEach => word = word.iter().flat_map(|byte| vec![*byte, *byte]).collect(),
+            Function::RejectUnlessShorter(length) => if word.len() >= length {
+                return None;
+            },
+            Function::RejectUnlessLonger(length) => if word.len() <= length {
+                return None;
+            },
+            Function::RejectUnlessLength(length) => if word.len() != length {
+                return None;
+            },
+            Function::RejectContains(byte) => if word.contains(&byte) {
+                return None;
+            },
+            Function::RejectUnlessContains(byte) => if !word.contains(&byte) {
+                return None;
+            },
+            Function::RejectUnlessStarts(byte) => if word.first() != Some(&byte) {
+                return None;
+            },
+            Function::RejectUnlessEnds(byte) => if word.last() != Some(&byte) {
+                return None;
+            }
+        }
+        Some(word)
+    }
+}
+
+/// One line of functions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Rule {
+    functions: Vec<Function>,
+    source: String
+}
+
+impl Rule {
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /**
+     * The mangled word, None if a rejection function dropped it or nothing is left of it.
+     */
+    pub fn apply(&self, word: &[u8]) -> Option<Vec<u8>> {
+        let word = self.functions.iter().try_fold(word.to_vec(), |word, function| function.apply(word))?;
+        if word.is_empty() {
+            None
+        } else {
+            Some(word)
+        }
+    }
+}
+
+impl FromStr for Rule {
+    type Err = WarpError;
+
+    fn from_str(line: &str) -> Result<Rule, WarpError> {
+        let bytes = line.as_bytes();
+        let mut functions = vec![];
+        let mut index = 0;
+        while index < bytes.len() {
+            let name = bytes[index];
+            index += 1;
+            if name == b' ' || name == b'\t' {
+                continue;
+            }
+            // Every argument byte of the function, in order.
+            let mut argument = |count: usize| -> Result<&[u8], WarpError> {
+                let arguments = bytes.get(index..index + count)
+                    .ok_or_else(|| WarpError::InvalidPattern(format!("{} needs {} more characters in rule [{}]", name as char, count, line)))?;
+                index += count;
+                Ok(arguments)
+            };
+            let position_of = |byte: u8| position(byte)
+                .ok_or_else(|| WarpError::InvalidPattern(format!("{} is not a position in rule [{}]", byte as char, line)));
+            let function = match name {
+                b':' => Function::Nothing,
+                b'l' => Function::Lower,
+                b'u' => Function::Upper,
+                b'c' => Function::Capitalize,
+                b'C' => Function::InvertCapitalize,
+                b't' => Function::ToggleAll,
+                b'T' => Function::Toggle(position_of(argument(1)?[0])?),
+                b'r' => Function::Reverse,
+                b'd' => Function::Duplicate,
+                b'p' => Function::DuplicateTimes(position_of(argument(1)?[0])?),
+                b'f' => Function::Reflect,
+                b'{' => Function::RotateLeft,
+                b'}' => Function::RotateRight,
+                b'$' => Function::Append(argument(1)?[0]),
+                b'^' => Function::Prepend(argument(1)?[0]),
+                b'[' => Function::DeleteFirst,
+                b']' => Function::DeleteLast,
+                b'D' => Function::DeleteAt(position_of(argument(1)?[0])?),
+                b'\'' => Function::Truncate(position_of(argument(1)?[0])?),
+                b'x' => {
+                    let arguments = argument(2)?;
+                    Function::Extract(position_of(arguments[0])?, position_of(arguments[1])?)
+                },
+                b'O' => {
+                    let arguments = argument(2)?;
+                    Function::Omit(position_of(arguments[0])?, position_of(arguments[1])?)
+                },
+                b'i' => {
+                    let arguments = argument(2)?;
+                    Function::Insert(position_of(arguments[0])?, arguments[1])
+                },
+                b'o' => {
+                    let arguments = argument(2)?;
+                    Function::Overwrite(position_of(arguments[0])?, arguments[1])
+                },
+                b's' => {
+                    let arguments = argument(2)?;
+                    Function::Replace(arguments[0], arguments[1])
+                },
+                b'@' => Function::Purge(argument(1)?[0]),
+                b'z' => Function::RepeatFirst(position_of(argument(1)?[0])?),
+                b'Z' => Function::RepeatLast(position_of(argument(1)?[0])?),
+                b'q' => Function::DoubleEach,
+                b'<' => Function::RejectUnlessShorter(position_of(argument(1)?[0])?),
+                b'>' => Function::RejectUnlessLonger(position_of(argument(1)?[0])?),
+                b'_' => Function::RejectUnlessLength(position_of(argument(1)?[0])?),
+                b'!' => Function::RejectContains(argument(1)?[0]),
+                b'/' => Function::RejectUnlessContains(argument(1)?[0]),
+                b'(' => Function::RejectUnlessStarts(argument(1)?[0]),
+                b')' => Function::RejectUnlessEnds(argument(1)?[0]),
+                _ => return Err(WarpError::InvalidPattern(format!("unknown rule function {} in [{}]", name as char, line)))
+            };
+            functions.push(function);
+        }
+        if functions.is_empty() {
+            return Err(WarpError::InvalidPattern("a rule needs at least one function, : for none".to_owned()));
+        }
+        Ok(Rule { functions, source: line.to_owned() })
+    }
+}
+
+/// The rules every word goes through, in order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RuleSet {
+    rules: Vec<Rule>
+}
+
+impl RuleSet {
+    pub fn new() -> RuleSet {
+        RuleSet::default()
+    }
+
+    pub fn add(&mut self, rule: &str) -> Result<(), WarpError> {
+        self.rules.push(rule.parse()?);
+        Ok(())
+    }
+
+    /**
+     * Adds one rule per line. Empty lines and lines starting with # are skipped, a rule that does
+     * not parse fails the whole file with its line number.
+     */
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, WarpError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut added = 0;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let rule = line.trim_end_matches(&['\r', '\n'][..]);
+            if rule.trim().is_empty() || rule.starts_with('#') {
+                continue;
+            }
+            self.add(rule).map_err(|error| WarpError::InvalidPattern(format!("line {}: {}", index + 1, error)))?;
+            added += 1;
+        }
+        Ok(added)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /**
+     * Every distinct candidate the rules make of one word, in rule order. Two rules often agree,
+     * : and l on a lowercase word for example, and a duplicate would only cost another derivation.
+     */
+    pub fn apply(&self, word: &[u8]) -> Vec<Vec<u8>> {
+        let mut seen = HashSet::new();
+        self.rules.iter()
+            .filter_map(|rule| rule.apply(word))
+            .filter(|candidate| seen.insert(candidate.clone()))
+            .collect()
+    }
+
+    /**
+     * Applies the rules to every word of a candidate source, errors are passed on as they are.
+     */
+    pub fn mangle<I>(&self, words: I) -> Mangled<'_, I::IntoIter>
+        where I: IntoIterator<Item = Result<Vec<u8>, WarpError>> {
+        Mangled { rules: self, words: words.into_iter(), pending: VecDeque::new() }
+    }
+}
+
+/// The candidates of RuleSet::mangle.
+pub struct Mangled<'a, I> {
+    rules: &'a RuleSet,
+    words: I,
+    pending: VecDeque<Vec<u8>>
+}
+
+impl<'a, I: Iterator<Item = Result<Vec<u8>, WarpError>>> Iterator for Mangled<'a, I> {
+    type Item = Result<Vec<u8>, WarpError>;
+
+    fn next(&mut self) -> Option<Result<Vec<u8>, WarpError>> {
+        loop {
+            if let Some(candidate) = self.pending.pop_front() {
+                return Some(Ok(candidate));
+            }
+            match self.words.next()? {
+                Ok(word) => self.pending.extend(self.rules.apply(&word)),
+                Err(error) => return Some(Err(error))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_rule_functions() {
+    let vectors = vec![
+        // Rule, word, result
+        (":", "p@ssW0rd", Some("p@ssW0rd")),
+        ("l", "p@ssW0rd", Some("p@ssw0rd")),
+        ("u", "p@ssW0rd", Some("P@SSW0RD")),
+        ("c", "p@ssW0rd", Some("P@ssw0rd")),
+        ("C", "p@ssW0rd", Some("p@SSW0RD")),
+        ("t", "p@ssW0rd", Some("P@SSw0RD")),
+        ("T0", "p@ssW0rd", Some("P@ssW0rd")),
+        ("TZ", "p@ssW0rd", Some("p@ssW0rd")),
+        ("r", "p@ssW0rd", Some("dr0Wss@p")),
+        ("d", "p@ssW0rd", Some("p@ssW0rdp@ssW0rd")),
+        ("p2", "abc", Some("abcabcabc")),
+        ("f", "abc", Some("abccba")),
+        ("{", "p@ssW0rd", Some("@ssW0rdp")),
+        ("}", "p@ssW0rd", Some("dp@ssW0r")),
+        ("$1", "p@ssW0rd", Some("p@ssW0rd1")),
+        ("^1", "p@ssW0rd", Some("1p@ssW0rd")),
+        ("[", "p@ssW0rd", Some("@ssW0rd")),
+        ("]", "p@ssW0rd", Some("p@ssW0r")),
+        ("D3", "p@ssW0rd", Some("p@sW0rd")),
+        ("'4", "p@ssW0rd", Some("p@ss")),
+        ("x04", "p@ssW0rd", Some("p@ss")),
+        ("O12", "p@ssW0rd", Some("psW0rd")),
+        ("i4!", "p@ssW0rd", Some("p@ss!W0rd")),
+        ("o3$", "p@ssW0rd", Some("p@s$W0rd")),
+        ("ss$", "p@ssW0rd", Some("p@$$W0rd")),
+        ("@s", "p@ssW0rd", Some("p@W0rd")),
+        ("z2", "abc", Some("aaabc")),
+        ("Z2", "abc", Some("abccc")),
+        ("q", "abc", Some("aabbcc")),
+        ("<9", "p@ssW0rd", Some("p@ssW0rd")),
+        ("<8", "p@ssW0rd", None),
+        (">7", "p@ssW0rd", Some("p@ssW0rd")),
+        (">8", "p@ssW0rd", None),
+        ("_8", "p@ssW0rd", Some("p@ssW0rd")),
+        ("_7", "p@ssW0rd", None),
+        ("!@", "p@ssW0rd", None),
+        ("/@", "p@ssW0rd", Some("p@ssW0rd")),
+        ("(p", "p@ssW0rd", Some("p@ssW0rd")),
+        ("(d", "p@ssW0rd", None),
+        (")d", "p@ssW0rd", Some("p@ssW0rd")),
+        // Whole rules, spaces are ignored
+        ("c $1 $2 $3", "dutch", Some("Dutch123")),
+        ("sa@ se3 so0 si1", "passwordie", Some("p@ssw0rd13")),
+        ("r c", "abc", Some("Cba")),
+        ("[ ]", "a", None),
+        ("$  $!", "hi", Some("hi !"))
+    ];
+    for tuple in vectors {
+        println!("Testing rule [{}] on [{}]", tuple.0, tuple.1);
+        let rule: Rule = tuple.0.parse().unwrap();
+        assert_eq!(tuple.2.map(|result| result.as_bytes().to_vec()), rule.apply(tuple.1.as_bytes()));
+    }
+}
+
+#[test]
+fn test_rule_length_limit() {
+    let rule: Rule = "pZpZpZpZpZpZpZ".parse().unwrap();
+    assert_eq!(None, rule.apply(b"a"));
+    let word = vec![b'a'; 255];
+    assert_eq!(Some(vec![b'a'; 256]), "$a".parse::<Rule>().unwrap().apply(&word));
+    assert_eq!(None, "$a$a".parse::<Rule>().unwrap().apply(&word));
+    assert_eq!(None, "d".parse::<Rule>().unwrap().apply(&word));
+    assert_eq!(None, ":".parse::<Rule>().unwrap().apply(&[b'a'; 257]));
+}
+
+#[test]
+fn test_invalid_rules() {
+    for rule in &["", "   ", "$", "T", "Ta", "x1", "i1", "s", "K", "p!"] {
+        println!("Testing invalid rule [{}]", rule);
+        assert!(rule.parse::<Rule>().is_err());
+    }
+}
+
+#[test]
+fn test_rule_set_drops_duplicates() {
+    let mut rules = RuleSet::new();
+    for rule in &[":", "l", "c", "u", "$1", "<5"] {
+        rules.add(rule).unwrap();
+    }
+    assert_eq!(6, rules.len());
+    assert_eq!(vec![b"password".to_vec(), b"Password".to_vec(), b"PASSWORD".to_vec(), b"password1".to_vec()], rules.apply(b"password"));
+    assert_eq!(vec![b"abc".to_vec(), b"Abc".to_vec(), b"ABC".to_vec(), b"abc1".to_vec()], rules.apply(b"abc"));
+
+    let words = vec![Ok(b"abc".to_vec()), Ok(b"ABC".to_vec()), Err(WarpError::Io("broken".to_owned()))];
+    let mangled: Vec<Result<Vec<u8>, WarpError>> = rules.mangle(words).collect();
+    assert_eq!(4 + 4 + 1, mangled.len());
+    assert_eq!(Ok(b"ABC".to_vec()), mangled[4]);
+    assert_eq!(Ok(b"abc".to_vec()), mangled[5]);
+    assert_eq!(Err(WarpError::Io("broken".to_owned())), mangled[8]);
+}
+
+#[test]
+fn test_load_rule_file() {
+    use std::env;
+    use std::fs;
+
+    let path = env::temp_dir().join("rust-warpwallet-test.rule");
+    fs::write(&path, "# leetspeak\r\n:\r\n\r\nsa@ so0\r\n$1 $!\n").unwrap();
+    let mut rules = RuleSet::new();
+    assert_eq!(Ok(3), rules.load_file(&path));
+    assert_eq!(vec![b"warp".to_vec(), b"w@rp".to_vec(), b"warp1!".to_vec()], rules.apply(b"warp"));
+
+    fs::write(&path, ":\nc\nK\n").unwrap();
+    match RuleSet::new().load_file(&path) {
+        Err(WarpError::InvalidPattern(reason)) => assert!(reason.starts_with("line 3:")),
+        other => panic!("expected an invalid pattern error, got {:?}", other)
+    }
+    fs::remove_file(&path).unwrap();
+}
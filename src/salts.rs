@@ -0,0 +1,151 @@
+// Variants of a salt, usually an email address, for when the passphrase is right but the salt the
+// wallet was made with is not quite the one remembered. In order:
+//      the salt as given, trimmed, lowercased and capitalized
+//      gmail.com and googlemail.com: without the dots of the local part, without a +tag, both, and
+//          on the other domain
+//      the local part with each of the given +tags
+//      gmail.com and googlemail.com: one dot added anywhere in the local part
+//      a trailing space, a trailing newline and a leading space on the trimmed and lowercased salt
+// Every variant is tried once, at its first place.
+
+use error::WarpError;
+#[cfg(test)]
+use keyspace;
+
+const GMAIL_DOMAINS: [&str; 2] = ["gmail.com", "googlemail.com"];
+
+/// A salt to expand, and the +tags to try on it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SaltVariants {
+    base: String,
+    plus_tags: Vec<String>
+}
+
+impl SaltVariants {
+    pub fn new(base: &str) -> Result<SaltVariants, WarpError> {
+        if base.trim().is_empty() {
+            return Err(WarpError::InvalidPattern("there are no variants of an empty salt".to_owned()));
+        }
+        Ok(SaltVariants { base: base.to_owned(), plus_tags: vec![] })
+    }
+
+    /**
+     * Tags to add to the local part, local+tag@domain. A leading + is optional.
+     */
+    pub fn with_plus_tags<S: AsRef<str>>(self, plus_tags: &[S]) -> SaltVariants {
+        let plus_tags = plus_tags.iter()
+            .map(|tag| tag.as_ref().trim_start_matches('+').to_owned())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        SaltVariants { plus_tags, ..self }
+    }
+
+    /**
+     * Every variant, most plausible first, the salt as given always first.
+     */
+    pub fn variants(&self) -> Vec<Vec<u8>> {
+        let mut variants: Vec<String> = vec![];
+        let mut add = |variant: String| if !variants.contains(&variant) {
+            variants.push(variant);
+        };
+
+        let trimmed = self.base.trim().to_owned();
+        let lowercase = trimmed.to_lowercase();
+        add(self.base.clone());
+        add(trimmed.clone());
+        add(lowercase.clone());
+        add(capitalized(&lowercase));
+
+        let address = split_address(&lowercase);
+        if let Some((local, domain)) = address {
+            let untagged = local.split('+').next().expect("split always yields one part");
+            let dotless = local.replace('.', "");
+            let plain = untagged.replace('.', "");
+            if GMAIL_DOMAINS.contains(&domain) {
+                for domain in other_domains_last(domain) {
+                    for local in &[local, &dotless, untagged, &plain] {
+                        add(format!("{}@{}", local, domain));
+                    }
+                }
+            }
+            for tag in &self.plus_tags {
+                add(format!("{}+{}@{}", untagged, tag, domain));
+                if GMAIL_DOMAINS.contains(&domain) {
+                    add(format!("{}+{}@{}", plain, tag, domain));
+                }
+            }
+            if GMAIL_DOMAINS.contains(&domain) {
+                for split in 1..plain.len() {
+                    if plain.is_char_boundary(split) {
+                        add(format!("{}.{}@{}", &plain[..split], &plain[split..], domain));
+                    }
+                }
+            }
+        }
+
+        for salt in &[&trimmed, &lowercase] {
+            add(format!("{} ", salt));
+            add(format!("{}\n", salt));
+            add(format!(" {}", salt));
+        }
+        variants.into_iter().map(String::into_bytes).collect()
+    }
+}
+
+fn capitalized(salt: &str) -> String {
+    let mut chars = salt.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+/**
+ * The local part and domain of an email address, None if it is not one.
+ */
+fn split_address(salt: &str) -> Option<(&str, &str)> {
+    let at = salt.rfind('@')?;
+    let (local, domain) = (&salt[..at], &salt[at + 1..]);
+    if local.is_empty() || domain.is_empty() {
+        return None;
+    }
+    Some((local, domain))
+}
+
+/**
+ * The gmail domain given first, then the other one.
+ */
+fn other_domains_last(domain: &str) -> Vec<&str> {
+    let mut domains = vec![domain];
+    domains.extend(GMAIL_DOMAINS.iter().filter(|other| **other != domain));
+    domains
+}
+
+#[test]
+fn test_salt_variants() {
+    let variants = keyspace::as_strings(SaltVariants::new("Me@Example.com ").unwrap().variants());
+    assert_eq!(vec![
+        "Me@Example.com ", "Me@Example.com", "me@example.com",
+        "Me@example.com", "Me@Example.com\n", " Me@Example.com", "me@example.com ", "me@example.com\n", " me@example.com"
+    ], variants);
+
+    let variants = keyspace::as_strings(SaltVariants::new("a@b.c").unwrap().with_plus_tags(&["+warp", ""]).variants());
+    assert_eq!(vec!["a@b.c", "A@b.c", "a+warp@b.c", "a@b.c ", "a@b.c\n", " a@b.c"], variants);
+
+    let variants = keyspace::as_strings(SaltVariants::new("keybase").unwrap().variants());
+    assert_eq!(vec!["keybase", "Keybase", "keybase ", "keybase\n", " keybase"], variants);
+}
+
+#[test]
+fn test_gmail_variants() {
+    let variants = keyspace::as_strings(SaltVariants::new("j.doe+btc@gmail.com").unwrap().with_plus_tags(&["warp"]).variants());
+    assert_eq!(vec![
+        "j.doe+btc@gmail.com", "J.doe+btc@gmail.com", "jdoe+btc@gmail.com", "j.doe@gmail.com", "jdoe@gmail.com",
+        "j.doe+btc@googlemail.com", "jdoe+btc@googlemail.com", "j.doe@googlemail.com", "jdoe@googlemail.com",
+        "j.doe+warp@gmail.com", "jdoe+warp@gmail.com", "jd.oe@gmail.com", "jdo.e@gmail.com",
+        "j.doe+btc@gmail.com ", "j.doe+btc@gmail.com\n", " j.doe+btc@gmail.com"
+    ], variants);
+
+    keyspace::assert_distinct(&variants);
+    assert!(SaltVariants::new(" \n").is_err());
+}
@@ -3,65 +3,96 @@ extern crate hex;
 extern crate crypto;
 
 use self::crypto::scrypt::{scrypt, ScryptParams};
+use error::WarpError;
 use std::thread;
 
-pub fn perform_scrypt(pass_phrase: Vec<u8>, salt: Vec<u8>, cpu_difficulty: u32, mem_difficulty: u32, parallel_difficulty: u32, key_length: usize) -> Vec<u8> {
+// dkLen <= (2^32 - 1) * hLen, with hLen 32 for HMAC-SHA256.
+pub const MAX_KEY_LENGTH: u64 = 0xffff_ffff * 32;
+
+pub fn perform_scrypt(pass_phrase: Vec<u8>, salt: Vec<u8>, cpu_difficulty: u32, mem_difficulty: u32, parallel_difficulty: u32, key_length: usize) -> Result<Vec<u8>, WarpError> {
+    let log_n = validate_scrypt_params(cpu_difficulty, mem_difficulty, parallel_difficulty, key_length)?;
+
     thread::spawn(move || {
         let mut to_store = vec![0u8; key_length];
-        let params: ScryptParams = ScryptParams::new(log2(cpu_difficulty), mem_difficulty, parallel_difficulty);
+        let params: ScryptParams = ScryptParams::new(log_n, mem_difficulty, parallel_difficulty);
         scrypt(&pass_phrase, &salt, &params, &mut to_store);
         // return the byte array
         to_store
-    }).join().unwrap()
+    }).join().map_err(WarpError::from)
 }
 
-fn log2(number: u32) -> u8 {
-    match number {
-        1_048_576 => 20,
-        524_288 => 19,
-        262_144 => 18,
-        131_072 => 17,
-        65_536 => 16,
-        32_768 => 15,
-        16_384 => 14,
-        8_192 => 13,
-        4096 => 12,
-        2048 => 11,
-        1024 => 10,
-        512 => 9,
-        256 => 8,
-        128 => 7,
-        64 => 6,
-        32 => 5,
-        16 => 4,
-        8 => 3,
-        4 => 2,
-        2 => 1,
-        _ => 0
+/**
+ * Checks everything ScryptParams::new would otherwise assert on (and the dkLen limit of
+ * RFC 7914), returning log2(N) for valid parameters.
+ */
+pub fn validate_scrypt_params(cpu_difficulty: u32, mem_difficulty: u32, parallel_difficulty: u32, key_length: usize) -> Result<u8, WarpError> {
+    let log_n = log2(cpu_difficulty)?;
+    if mem_difficulty == 0 || parallel_difficulty == 0 {
+        return Err(WarpError::InvalidKdfParameters("scrypt r and p must be at least 1".to_owned()));
+    }
+    // N < 2^(128 * r / 8)
+    if u64::from(log_n) >= u64::from(mem_difficulty) * 16 {
+        return Err(WarpError::InvalidKdfParameters(format!("scrypt N {} is too large for r {}", cpu_difficulty, mem_difficulty)));
+    }
+    // p <= ((2^32 - 1) * 32) / (128 * r)
+    if u64::from(mem_difficulty) * u64::from(parallel_difficulty) >= 0x4000_0000 {
+        return Err(WarpError::InvalidKdfParameters("scrypt r * p must be below 2^30".to_owned()));
+    }
+    if scrypt_memory_bytes(cpu_difficulty, mem_difficulty) > usize::MAX as u64 {
+        return Err(WarpError::InvalidKdfParameters("scrypt N * r does not fit in memory".to_owned()));
     }
+    if key_length == 0 || key_length as u64 > MAX_KEY_LENGTH {
+        return Err(WarpError::InvalidKdfParameters(format!("key length {} is out of range", key_length)));
+    }
+    Ok(log_n)
+}
 
-    // The dynamic way of doing this:
-//    thread::spawn(move || {
-//        let nr = number as f32;
-//        nr.log2() as u8
-//    }).join().unwrap()
+/**
+ * Size of the V array scrypt fills: 128 * r * N bytes. This dominates the memory use.
+ */
+pub fn scrypt_memory_bytes(cpu_difficulty: u32, mem_difficulty: u32) -> u64 {
+    128 * u64::from(mem_difficulty) * u64::from(cpu_difficulty)
+}
+
+/**
+ * log2 of a power of two N. Scrypt needs N > 1, so 1 and non powers of two are an error.
+ */
+pub fn log2(number: u32) -> Result<u8, WarpError> {
+    if number < 2 || !number.is_power_of_two() {
+        return Err(WarpError::InvalidKdfParameters(format!("scrypt N {} is not a power of two above 1", number)));
+    }
+    Ok(number.trailing_zeros() as u8)
 }
 
 #[test]
 fn test_log() {
     let vectors: Vec<(u8, u32)> = vec![
+        ( 1, 2 ),
         ( 4, 16 ),
         ( 10, 1_024 ),
         ( 14, 16_384 ),
         ( 16, 65_536 ),
         ( 18, 262_144 ),
-        ( 20, 1_048_576 )
+        ( 20, 1_048_576 ),
+        ( 24, 16_777_216 ),
+        ( 31, 2_147_483_648 )
     ];
     for tuple in vectors {
-        assert_eq!(tuple.0, log2(tuple.1));
+        assert_eq!(tuple.0, log2(tuple.1).unwrap());
+    }
+    for invalid in vec![0, 1, 3, 3_000, 262_143, 262_145, u32::MAX] {
+        assert!(log2(invalid).is_err());
     }
 }
 
+#[test]
+fn test_scrypt_memory_bytes() {
+    // 128 * r * N
+    assert_eq!(268_435_456, scrypt_memory_bytes(262_144, 8));
+    assert_eq!(16_777_216, scrypt_memory_bytes(16_384, 8));
+    assert_eq!(2_048, scrypt_memory_bytes(16, 1));
+}
+
 #[test]
 fn test_scrypt_vectors() {
     let vectors = vec![
@@ -73,7 +104,28 @@ fn test_scrypt_vectors() {
     ];
     for tuple in vectors {
         println!("Testing phrase [{}] with salt [{}] and {} iterations.", tuple.1, tuple.2, tuple.3);
-        let result = hex::encode(perform_scrypt(tuple.1.as_bytes().to_vec(), tuple.2.as_bytes().to_vec(), tuple.3, tuple.4, tuple.5, tuple.6));
+        let result = hex::encode(perform_scrypt(tuple.1.as_bytes().to_vec(), tuple.2.as_bytes().to_vec(), tuple.3, tuple.4, tuple.5, tuple.6).unwrap());
         assert_eq!(tuple.0, result);
     }
 }
+
+#[test]
+fn test_invalid_scrypt_parameters() {
+    let vectors: Vec<(u32, u32, u32, usize)> = vec![
+        // iterationCount (N), mem_difficulty (R), parallel_difficulty (P), keyLength
+        ( 1, 8, 1, 32 ),
+        ( 3_000, 8, 1, 32 ),
+        ( 16, 0, 1, 32 ),
+        ( 16, 1, 0, 32 ),
+        ( 65_536, 1, 1, 32 ),
+        ( 16, 1_048_576, 1_024, 32 ),
+        ( 16, 1, 1, 0 )
+    ];
+    for tuple in vectors {
+        println!("Testing N [{}], r [{}], p [{}] and dkLen [{}].", tuple.0, tuple.1, tuple.2, tuple.3);
+        match perform_scrypt(b"password".to_vec(), b"salt".to_vec(), tuple.0, tuple.1, tuple.2, tuple.3) {
+            Err(WarpError::InvalidKdfParameters(_)) => (),
+            other => panic!("expected invalid KDF parameters, got {:?}", other)
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// Runs passphrase candidates through the warp derivation on a fixed number of worker threads and
+// checks every resulting key against a TargetSet. With more than one salt every candidate is derived
+// once per salt, the salts of one candidate handed out before the next candidate is pulled.
+// Candidates are pulled from the iterator only as fast as the workers take them, so a generator
+// or wordlist of any size is never collected in memory.
+
+use error::WarpError;
+use params::WarpParams;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use targets::{TargetMatch, TargetSet};
+use time::{Duration, PreciseTime};
+use warpwallet::WarpWallet;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchConfig {
+    /// Salts every candidate is derived with, in order.
+    pub salts: Vec<Vec<u8>>,
+    pub params: WarpParams,
+    /// Worker threads, each needs the scrypt memory of one derivation.
+    pub threads: usize,
+    /// How often a Progress event is reported.
+    pub report_interval: Duration
+}
+
+impl SearchConfig {
+    pub fn new(salt: &[u8], params: WarpParams) -> SearchConfig {
+        SearchConfig { salts: vec![salt.to_vec()], params, threads: 4, report_interval: Duration::seconds(10) }
+    }
+}
+
+/// Candidates checked so far and how long that took.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Throughput {
+    /// Derivations, one per candidate and salt.
+    pub candidates: u64,
+    pub found: u64,
+    pub elapsed: Duration
+}
+
+impl Throughput {
+    pub fn per_second(&self) -> f64 {
+        match self.elapsed.num_microseconds() {
+            Some(micros) if micros > 0 => self.candidates as f64 * 1_000_000.0 / micros as f64,
+            _ => 0.0
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchEvent {
+    Found { candidate: Vec<u8>, salt: Vec<u8>, target_match: TargetMatch },
+    /// Deriving or matching this candidate failed, the search goes on.
+    Failed { candidate: Vec<u8>, salt: Vec<u8>, error: WarpError },
+    Progress(Throughput)
+}
+
+/// A candidate and the index of the salt to derive it with.
+type Job = (Vec<u8>, usize);
+type CandidateResult = (Job, Result<Vec<TargetMatch>, WarpError>);
+
+fn check_candidate(candidate: &[u8], targets: &TargetSet, salt: &[u8], params: WarpParams) -> Result<Vec<TargetMatch>, WarpError> {
+    let exponent = WarpWallet::from_bytes(candidate.to_vec(), salt.to_vec()).with_params(params).derive_exponent()?;
+    targets.matches_exponent(&exponent)
+}
+
+/**
+ * Checks every candidate and calls on_event for each match, failure and progress report.
+ * Stops at the first error of the candidate source itself, a wordlist that can not be read for example.
+ */
+pub fn run<I, F>(candidates: I, targets: Arc<TargetSet>, config: &SearchConfig, mut on_event: F) -> Result<Throughput, WarpError>
+    where I: IntoIterator<Item = Result<Vec<u8>, WarpError>>, F: FnMut(SearchEvent) {
+    config.params.validate()?;
+    if config.salts.is_empty() {
+        return Err(WarpError::InvalidPattern("a search needs at least one salt".to_owned()));
+    }
+    let salts = Arc::new(config.salts.clone());
+    let threads = config.threads.max(1);
+    let (job_tx, job_rx) = mpsc::sync_channel::<Job>(threads * 2);
+    let job_rx = Arc::new(Mutex::new(job_rx));
+    let (result_tx, result_rx) = mpsc::channel::<CandidateResult>();
+
+    let workers: Vec<thread::JoinHandle<()>> = (0..threads).map(|_| {
+        let job_rx = job_rx.clone();
+        let result_tx = result_tx.clone();
+        let targets = targets.clone();
+        let salts = salts.clone();
+        let params = config.params;
+        thread::spawn(move || loop {
+            let job = match job_rx.lock() {
+                Ok(job_rx) => match job_rx.recv() {
+                    Ok(job) => job,
+                    Err(_) => break
+                },
+                Err(_) => break
+            };
+            let result = check_candidate(&job.0, &targets, &salts[job.1], params);
+            if result_tx.send((job, result)).is_err() {
+                break;
+            }
+        })
+    }).collect();
+    drop(result_tx);
+
+    let start = PreciseTime::now();
+    let mut last_report = start;
+    let mut throughput = Throughput { candidates: 0, found: 0, elapsed: Duration::zero() };
+    let mut handle = |((candidate, salt), result): CandidateResult, throughput: &mut Throughput| {
+        throughput.candidates += 1;
+        let salt = salts[salt].clone();
+        match result {
+            Ok(found) => for target_match in found {
+                throughput.found += 1;
+                on_event(SearchEvent::Found { candidate: candidate.clone(), salt: salt.clone(), target_match });
+            },
+            Err(error) => on_event(SearchEvent::Failed { candidate, salt, error })
+        }
+        let now = PreciseTime::now();
+        if last_report.to(now) >= config.report_interval {
+            last_report = now;
+            throughput.elapsed = start.to(now);
+            on_event(SearchEvent::Progress(*throughput));
+        }
+    };
+
+    let mut source_error = None;
+    'candidates: for candidate in candidates {
+        let candidate = match candidate {
+            Ok(candidate) => candidate,
+            Err(error) => {
+                source_error = Some(error);
+                break;
+            }
+        };
+        for salt in 0..salts.len() {
+            if job_tx.send((candidate.clone(), salt)).is_err() {
+                // Every worker is gone, which only happens when they panicked.
+                break 'candidates;
+            }
+        }
+        while let Ok(result) = result_rx.try_recv() {
+            handle(result, &mut throughput);
+        }
+    }
+    drop(job_tx);
+    for result in result_rx.iter() {
+        handle(result, &mut throughput);
+    }
+
+    for worker in workers {
+        worker.join()?;
+    }
+    if let Some(error) = source_error {
+        return Err(error);
+    }
+    throughput.elapsed = start.to(PreciseTime::now());
+    Ok(throughput)
+}
+
+#[test]
+fn test_search_finds_wordlist_candidate() {
+    let params = WarpParams { scrypt_iterations: 1_024, pbkdf2_iterations: 16, ..WarpParams::keybase_v1() };
+    let keypair = WarpWallet::new("Dutch Power Cows", "a@b.c").with_params(params).derive().unwrap();
+    let targets = TargetSet::from_addresses(vec![keypair.compressed.address.as_str(), "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]).unwrap();
+
+    let candidates: Vec<Result<Vec<u8>, WarpError>> = vec!["password", "Dutch Power Cows", "hunter2", "letmein", "\u{1F511}"]
+        .into_iter()
+        .map(|candidate| Ok(candidate.as_bytes().to_vec()))
+        .collect();
+    let mut config = SearchConfig::new(b"a@b.c", params);
+    config.threads = 2;
+    let mut found = vec![];
+    let throughput = run(candidates, Arc::new(targets), &config, |event| match event {
+        SearchEvent::Found { candidate, target_match, .. } => found.push((candidate, target_match)),
+        SearchEvent::Failed { candidate, error, .. } => panic!("{:?} failed: {}", candidate, error),
+        SearchEvent::Progress(_) => ()
+    }).unwrap();
+
+    assert_eq!(5, throughput.candidates);
+    assert_eq!(1, throughput.found);
+    assert_eq!(1, found.len());
+    assert_eq!(b"Dutch Power Cows".to_vec(), found[0].0);
+    assert_eq!(keypair.compressed.address.as_str(), found[0].1.target.address);
+    assert_eq!(keypair.secret_exponent, found[0].1.secret_exponent);
+}
+
+#[test]
+fn test_search_every_salt() {
+    let params = WarpParams { scrypt_iterations: 1_024, pbkdf2_iterations: 16, ..WarpParams::keybase_v1() };
+    let keypair = WarpWallet::new("hunter2", "me@example.com").with_params(params).derive().unwrap();
+    let targets = TargetSet::from_addresses(vec![keypair.uncompressed.address.as_str()]).unwrap();
+
+    let candidates = vec![Ok(b"password".to_vec()), Ok(b"hunter2".to_vec())];
+    let mut config = SearchConfig::new(b"Me@Example.com", params);
+    config.salts.push(b"me@example.com".to_vec());
+    config.salts.push(b"me@example.com ".to_vec());
+    let mut found = vec![];
+    let throughput = run(candidates, Arc::new(targets), &config, |event| if let SearchEvent::Found { candidate, salt, .. } = event {
+        found.push((candidate, salt));
+    }).unwrap();
+    assert_eq!(2 * 3, throughput.candidates);
+    assert_eq!(vec![(b"hunter2".to_vec(), b"me@example.com".to_vec())], found);
+
+    config.salts.clear();
+    assert!(run(vec![], Arc::new(TargetSet::new()), &config, |_| ()).is_err());
+}
+
+#[test]
+fn test_search_stops_at_source_error() {
+    let params = WarpParams { scrypt_iterations: 1_024, pbkdf2_iterations: 16, ..WarpParams::keybase_v1() };
+    let targets = TargetSet::from_addresses(vec!["1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]).unwrap();
+    let candidates = vec![Ok(b"one".to_vec()), Err(WarpError::Io("broken".to_owned())), Ok(b"two".to_vec())];
+    let result = run(candidates, Arc::new(targets), &SearchConfig::new(b"a@b.c", params), |_| ());
+    assert_eq!(Err(WarpError::Io("broken".to_owned())), result);
+
+    let invalid = WarpParams { scrypt_iterations: 1_000, ..params };
+    let result = run(vec![], Arc::new(TargetSet::new()), &SearchConfig::new(b"a@b.c", invalid), |_| ());
+    assert!(result.is_err());
+}
@@ -1,7 +1,10 @@
 
 extern crate crypto;
+extern crate hex;
 
 use sha256::crypto::digest::Digest;
+use sha256::crypto::ripemd160::Ripemd160;
+use sha256::crypto::sha3::Sha3;
 
 pub fn hash256(input: &str) -> String {
     let mut sha = crypto::sha2::Sha256::new();
@@ -9,6 +12,50 @@ pub fn hash256(input: &str) -> String {
     sha.result_str()
 }
 
+pub fn sha256(input: &[u8]) -> Vec<u8> {
+    let mut sha = crypto::sha2::Sha256::new();
+    sha.input(input);
+    let mut output = vec![0u8; 32];
+    sha.result(&mut output);
+    output
+}
+
+/**
+ * RIPEMD160(SHA256(input)), the hash inside P2PKH and P2WPKH outputs.
+ */
+pub fn hash160(input: &[u8]) -> Vec<u8> {
+    let mut ripemd = Ripemd160::new();
+    ripemd.input(&sha256(input));
+    let mut output = vec![0u8; 20];
+    ripemd.result(&mut output);
+    output
+}
+
+/**
+ * BIP340 tagged hash: SHA256(SHA256(tag) || SHA256(tag) || input).
+ */
+pub fn tagged_hash(tag: &str, input: &[u8]) -> Vec<u8> {
+    let tag_hash = sha256(tag.as_bytes());
+    let mut sha = crypto::sha2::Sha256::new();
+    sha.input(&tag_hash);
+    sha.input(&tag_hash);
+    sha.input(input);
+    let mut output = vec![0u8; 32];
+    sha.result(&mut output);
+    output
+}
+
+/**
+ * The original Keccak-256 Ethereum uses, not the padding-changed NIST SHA3-256.
+ */
+pub fn keccak256(input: &[u8]) -> Vec<u8> {
+    let mut keccak = Sha3::keccak256();
+    keccak.input(input);
+    let mut output = vec![0u8; 32];
+    keccak.result(&mut output);
+    output
+}
+
 #[test]
 fn test_sha_256_hashing() {
     let test_vectors = vec![
@@ -23,4 +70,43 @@ fn test_sha_256_hashing() {
         println!("testing {}", tuple.1);
         assert_eq!(tuple.0, self::hash256(tuple.1));
     };
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_hash160() {
+    let test_vectors = vec![
+        // hash160 hex string, public key hex string
+        ("751e76e8199196d454941c45d1b3a323f1433bd6", "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798"),
+        ("91b24bf9f5288532960ac687abb035127b1d28a5", "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8")
+    ];
+    for tuple in &test_vectors {
+        println!("testing {}", tuple.1);
+        assert_eq!(tuple.0, hex::encode(hash160(&hex::decode(tuple.1).unwrap())));
+    }
+}
+
+#[test]
+fn test_tagged_hash() {
+    let test_vectors = vec![
+        // tagged hash hex string, tag, input hex string
+        ("c216d352f5818b7b4beacd4ae0a26fe888080823d2a598856661bcd54f1b3713", "BIP0340/challenge", ""),
+        ("3cf5216d476a5e637bf0da674e50ddf55c403270dd36494dfcca438132fa30e7", "TapTweak", "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+    ];
+    for tuple in &test_vectors {
+        println!("testing {} {}", tuple.1, tuple.2);
+        assert_eq!(tuple.0, hex::encode(tagged_hash(tuple.1, &hex::decode(tuple.2).unwrap())));
+    }
+}
+
+#[test]
+fn test_keccak256() {
+    let test_vectors = vec![
+        // keccak256 hex string, input
+        ("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470", ""),
+        ("4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45", "abc")
+    ];
+    for tuple in &test_vectors {
+        println!("testing {}", tuple.1);
+        assert_eq!(tuple.0, hex::encode(keccak256(tuple.1.as_bytes())));
+    }
+}
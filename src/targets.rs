@@ -0,0 +1,412 @@
+// The addresses a search is looking for.
+// Addresses are decoded to the hash they commit to, so a Dogecoin or testnet address matches the same key
+// as the bitcoin one.
+
+extern crate bitcoin;
+extern crate hex;
+
+use self::bitcoin::util::base58::FromBase58;
+use bech32;
+use bitcoin::{p2sh_p2wpkh_script_hash, taproot_output_key, KeyFormat, SecretExponent};
+use coins::CoinProfile;
+use error::WarpError;
+use ethereum::EthereumAddress;
+use index::HashIndex;
+use sha256::{hash160, keccak256};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use warpwallet::DerivedKeypair;
+
+/// What an address pays to, and so which hash of a candidate key it is compared with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TargetKind {
+    /// P2PKH, hash160 of the public key in either format.
+    PubkeyHash,
+    /// P2SH, only P2SH-P2WPKH can be matched, hash160 of its redeem script.
+    ScriptHash,
+    /// P2WPKH, hash160 of the compressed public key.
+    WitnessPubkeyHash,
+    /// P2TR, the 32 byte x-only output key.
+    Taproot,
+    /// The last 20 bytes of keccak256 of the uncompressed public key.
+    Ethereum
+}
+
+impl fmt::Display for TargetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            TargetKind::PubkeyHash => write!(f, "p2pkh"),
+            TargetKind::ScriptHash => write!(f, "p2sh-p2wpkh"),
+            TargetKind::WitnessPubkeyHash => write!(f, "p2wpkh"),
+            TargetKind::Taproot => write!(f, "p2tr"),
+            TargetKind::Ethereum => write!(f, "ethereum")
+        }
+    }
+}
+
+/// One address to search for, with the hash it decodes to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub address: String,
+    pub kind: TargetKind,
+    pub hash: Vec<u8>
+}
+
+impl FromStr for Target {
+    type Err = WarpError;
+
+    fn from_str(address: &str) -> Result<Target, WarpError> {
+        let address = address.trim();
+        let (kind, hash) = if address.starts_with("0x") {
+            let ethereum: EthereumAddress = address.parse()?;
+            (TargetKind::Ethereum, hex::decode(&ethereum.as_str()[2..])?)
+        } else if let Some(hrp) = segwit_hrp_of(address) {
+            match bech32::decode_segwit_address(hrp, address)? {
+                (0, ref program) if program.len() == 20 => (TargetKind::WitnessPubkeyHash, program.clone()),
+                (1, ref program) if program.len() == 32 => (TargetKind::Taproot, program.clone()),
+                _ => return Err(WarpError::InvalidAddress(format!("{} is not a P2WPKH or P2TR address", address)))
+            }
+        } else {
+            let payload: Vec<u8> = FromBase58::from_base58check(address)?;
+            if payload.len() != 21 {
+                return Err(WarpError::InvalidAddress(format!("{} does not decode to a 20 byte hash", address)));
+            }
+            let coins = CoinProfile::all();
+            let kind = if coins.iter().any(|coin| coin.p2pkh_version == payload[0]) {
+                TargetKind::PubkeyHash
+            } else if coins.iter().any(|coin| coin.p2sh_version == payload[0]) {
+                TargetKind::ScriptHash
+            } else {
+                return Err(WarpError::InvalidAddress(format!("unknown version byte {:#04x} of {}", payload[0], address)));
+            };
+            (kind, payload[1..].to_vec())
+        };
+        Ok(Target { address: address.to_owned(), kind, hash })
+    }
+}
+
+impl Target {
+    /**
+     * The coin the address is encoded for, by its version byte or segwit hrp. None for Ethereum.
+     * Coins that share a version byte share a WIF version too, so the first one is as good as any.
+     */
+    pub fn coin(&self) -> Option<CoinProfile> {
+        match self.kind {
+            TargetKind::Ethereum => None,
+            TargetKind::WitnessPubkeyHash | TargetKind::Taproot => {
+                let hrp = segwit_hrp_of(&self.address)?;
+                CoinProfile::all().into_iter().find(|coin| coin.bech32_hrp == Some(hrp))
+            },
+            TargetKind::PubkeyHash | TargetKind::ScriptHash => {
+                let payload: Vec<u8> = FromBase58::from_base58check(&self.address).ok()?;
+                let version = *payload.first()?;
+                CoinProfile::all().into_iter().find(|coin| match self.kind {
+                    TargetKind::PubkeyHash => coin.p2pkh_version == version,
+                    _ => coin.p2sh_version == version
+                })
+            }
+        }
+    }
+}
+
+fn segwit_hrp_of(address: &str) -> Option<&'static str> {
+    let lowered = address.to_ascii_lowercase();
+    CoinProfile::all().into_iter()
+        .filter_map(|coin| coin.bech32_hrp)
+        .find(|hrp| lowered.starts_with(&format!("{}1", hrp)))
+}
+
+/// A target that matched, with what is needed to spend from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TargetMatch {
+    pub target: Target,
+    pub secret_exponent: SecretExponent,
+    /// The public key serialization the target commits to. Segwit targets are always compressed,
+    /// Ethereum ones uncompressed.
+    pub key_format: KeyFormat
+}
+
+/// Any number of targets, looked up by hash in memory or in memory-mapped index files.
+#[derive(Clone, Debug, Default)]
+pub struct TargetSet {
+    targets: HashMap<Vec<u8>, Vec<Target>>,
+    indexes: Vec<Arc<HashIndex>>,
+    count: usize,
+    has_taproot: bool
+}
+
+impl TargetSet {
+    pub fn new() -> TargetSet {
+        TargetSet::default()
+    }
+
+    pub fn from_addresses<I, S>(addresses: I) -> Result<TargetSet, WarpError>
+        where I: IntoIterator<Item = S>, S: AsRef<str> {
+        let mut targets = TargetSet::new();
+        for address in addresses {
+            targets.add(address.as_ref())?;
+        }
+        Ok(targets)
+    }
+
+    /**
+     * Adds an address, returning false if it already was a target.
+     */
+    pub fn add(&mut self, address: &str) -> Result<bool, WarpError> {
+        let target: Target = address.parse()?;
+        let same_hash = self.targets.entry(target.hash.clone()).or_default();
+        if same_hash.iter().any(|known| known.address == target.address) {
+            return Ok(false);
+        }
+        self.has_taproot |= target.kind == TargetKind::Taproot;
+        same_hash.push(target);
+        self.count += 1;
+        Ok(true)
+    }
+
+    /**
+     * Adds one address per line. Blank lines and lines starting with # are skipped, an invalid
+     * address fails the whole file with its line number. Returns the number of new targets.
+     */
+    pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, WarpError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut added = 0;
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            let address = line.trim();
+            if address.is_empty() || address.starts_with('#') {
+                continue;
+            }
+            match self.add(address) {
+                Ok(true) => added += 1,
+                Ok(false) => (),
+                Err(error) => return Err(WarpError::InvalidAddress(format!("line {}: {}", index + 1, error)))
+            }
+        }
+        Ok(added)
+    }
+
+    /**
+     * Adds every target of an index file. Targets in more than one index, or also added by address,
+     * count once per source in `len` but are reported once when they match.
+     */
+    pub fn add_index(&mut self, index: HashIndex) {
+        self.count += index.len();
+        self.indexes.push(Arc::new(index));
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /**
+     * Every target paying to any of the keypair's keys, in any coin or network.
+     */
+    pub fn matches(&self, keypair: &DerivedKeypair) -> Result<Vec<TargetMatch>, WarpError> {
+        self.match_public_keys(
+            &keypair.secret_exponent,
+            keypair.uncompressed.public_key.as_bytes(),
+            keypair.compressed.public_key.as_bytes()
+        )
+    }
+
+    /**
+     * Like matches, straight from the exponent. Only the public keys and their hashes are computed,
+     * no WIF or address is encoded, which is what a search over a large target list wants.
+     */
+    pub fn matches_exponent(&self, exponent: &SecretExponent) -> Result<Vec<TargetMatch>, WarpError> {
+        if self.is_empty() {
+            return Ok(vec![]);
+        }
+        let uncompressed = exponent.to_public_key(false)?;
+        let compressed = exponent.to_public_key(true)?;
+        self.match_public_keys(exponent, uncompressed.as_bytes(), compressed.as_bytes())
+    }
+
+    fn match_public_keys(&self, exponent: &SecretExponent, uncompressed: &[u8], compressed: &[u8]) -> Result<Vec<TargetMatch>, WarpError> {
+        if self.is_empty() {
+            return Ok(vec![]);
+        }
+        let compressed_hash = hash160(compressed);
+        let mut candidates = vec![
+            (TargetKind::PubkeyHash, KeyFormat::Uncompressed, hash160(uncompressed)),
+            (TargetKind::PubkeyHash, KeyFormat::Compressed, compressed_hash.clone()),
+            (TargetKind::WitnessPubkeyHash, KeyFormat::Compressed, compressed_hash),
+            (TargetKind::ScriptHash, KeyFormat::Compressed, p2sh_p2wpkh_script_hash(compressed)?),
+            (TargetKind::Ethereum, KeyFormat::Uncompressed, keccak256(&uncompressed[1..])[12..].to_vec())
+        ];
+        // Tweaking is an extra point multiplication, only worth it when something can match.
+        if self.has_taproot {
+            candidates.push((TargetKind::Taproot, KeyFormat::Compressed, taproot_output_key(compressed)?));
+        }
+
+        let mut found = vec![];
+        for (kind, key_format, hash) in candidates {
+            let mut targets: Vec<Target> = self.targets.get(&hash).cloned().unwrap_or_default();
+            for index in &self.indexes {
+                targets.extend(index.lookup(&hash));
+            }
+            for target in targets.into_iter().filter(|target| target.kind == kind) {
+                // The same address can be both on the command line and in an index.
+                let coin = target.coin();
+                if found.iter().any(|other: &TargetMatch| other.target.kind == kind && other.target.hash == target.hash && other.target.coin() == coin) {
+                    continue;
+                }
+                found.push(TargetMatch { target, secret_exponent: exponent.clone(), key_format });
+            }
+        }
+        Ok(found)
+    }
+}
+
+#[test]
+fn test_decode_targets() {
+    let vectors = vec![
+        // Address, kind, hash
+        ("1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMb", TargetKind::PubkeyHash, "7d6a305fb22e0e6013d93f4bb5e08589dd0b6a0d"),
+        ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", TargetKind::WitnessPubkeyHash, "751e76e8199196d454941c45d1b3a323f1433bd6"),
+        ("bc1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5sspknck9", TargetKind::Taproot, "da4710964f7852695de2da025290e24af6d8c281de5a0b902b7135fd9fd74d21"),
+        ("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf", TargetKind::Ethereum, "7e5f4552091a69125d5dfcb7b8c2659029395bdf")
+    ];
+    for tuple in vectors {
+        println!("Testing target [{}]", tuple.0);
+        let target: Target = tuple.0.parse().unwrap();
+        assert_eq!(tuple.1, target.kind);
+        assert_eq!(tuple.2, hex::encode(&target.hash));
+    }
+
+    let invalid = vec![
+        "1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMc",
+        "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t5",
+        "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+        "0x7E5F4552091A69125d5DfCb7b8C2659029395BDF",
+        "5KDtreMojMGybkQngM9MVY7GhAygvx7iZjL7dbcjQjrcMeULjWZ"
+    ];
+    for address in invalid {
+        println!("Testing invalid target [{}]", address);
+        assert!(address.parse::<Target>().is_err());
+    }
+}
+
+#[test]
+fn test_target_set_matches() {
+    let vectors = vec![
+        // Address of exponent b94d27..., kind, matching key format
+        ("1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMb", TargetKind::PubkeyHash, KeyFormat::Uncompressed),
+        ("12q7HJP6LFwMHFWCogVzjq7BsHt8tqWfur", TargetKind::PubkeyHash, KeyFormat::Compressed),
+        ("D6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs", TargetKind::PubkeyHash, KeyFormat::Compressed),
+        ("mhM4aMU59HNc4MypXFUNZkKWjHUqnce7ZM", TargetKind::PubkeyHash, KeyFormat::Compressed),
+        ("3KKCYKKJjFtxQa4nPSnk2g8EXPo62r3Pzr", TargetKind::ScriptHash, KeyFormat::Compressed),
+        ("bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz", TargetKind::WitnessPubkeyHash, KeyFormat::Compressed),
+        ("bc1pwwtzg63krwfvqkv5pca95kg50hkugxjwucaccp9xsl2ftltscmgqrg8gu9", TargetKind::Taproot, KeyFormat::Compressed),
+        ("0x09332B1E45e6172fB26E46B3DB4411201547560a", TargetKind::Ethereum, KeyFormat::Uncompressed)
+    ];
+    let exponent: SecretExponent = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".parse().unwrap();
+    let keypair = DerivedKeypair::from_exponent(exponent.clone(), CoinProfile::BITCOIN).unwrap();
+    for tuple in vectors {
+        println!("Testing target [{}]", tuple.0);
+        let targets = TargetSet::from_addresses(vec![tuple.0, "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]).unwrap();
+        assert_eq!(2, targets.len());
+        let found = targets.matches(&keypair).unwrap();
+        assert_eq!(1, found.len());
+        assert_eq!(tuple.0, found[0].target.address);
+        assert_eq!(tuple.1, found[0].target.kind);
+        assert_eq!(tuple.2, found[0].key_format);
+        assert_eq!(exponent, found[0].secret_exponent);
+        assert_eq!(found, targets.matches_exponent(&exponent).unwrap());
+    }
+
+    let targets = TargetSet::from_addresses(vec!["1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]).unwrap();
+    assert!(targets.matches(&keypair).unwrap().is_empty());
+    assert!(TargetSet::new().matches(&keypair).unwrap().is_empty());
+}
+
+#[test]
+fn test_target_set_with_index() {
+    use std::env;
+    use std::fs;
+    use std::io::Cursor;
+
+    let path = env::temp_dir().join("rust-warpwallet-test-targets.h160");
+    let addresses = "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA\nD6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs\n0x09332b1e45e6172fb26e46b3db4411201547560a\n";
+    HashIndex::build(Cursor::new(addresses), &path).unwrap();
+
+    let mut targets = TargetSet::from_addresses(vec!["bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz", "D6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs"]).unwrap();
+    targets.add_index(HashIndex::open(&path).unwrap().with_bloom_filter(10));
+    assert_eq!(5, targets.len());
+
+    let exponent: SecretExponent = "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".parse().unwrap();
+    let mut found: Vec<String> = targets.matches_exponent(&exponent).unwrap().into_iter()
+        .map(|found| found.target.address)
+        .collect();
+    found.sort();
+    assert_eq!(vec![
+        "0x09332B1E45e6172fB26E46B3DB4411201547560a",
+        "D6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs",
+        "bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz"
+    ], found);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_load_target_file() {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    let path = env::temp_dir().join("rust-warpwallet-test-targets.txt");
+    {
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "# keybase warp challenge").unwrap();
+        writeln!(file, "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA").unwrap();
+        writeln!(file).unwrap();
+        writeln!(file, "  bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz\r").unwrap();
+        writeln!(file, "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA").unwrap();
+    }
+    let mut targets = TargetSet::new();
+    assert_eq!(Ok(2), targets.load_file(&path));
+    assert_eq!(2, targets.len());
+
+    {
+        let mut file = File::create(&path).unwrap();
+        writeln!(file, "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA").unwrap();
+        writeln!(file, "not an address").unwrap();
+    }
+    match TargetSet::new().load_file(&path) {
+        Err(WarpError::InvalidAddress(reason)) => assert!(reason.starts_with("line 2:")),
+        other => panic!("expected an invalid address error, got {:?}", other)
+    }
+    fs::remove_file(&path).unwrap();
+
+    match TargetSet::new().load_file(&path) {
+        Err(WarpError::Io(_)) => (),
+        other => panic!("expected an i/o error, got {:?}", other)
+    }
+}
+
+#[test]
+fn test_target_coin() {
+    let vectors = vec![
+        // Address, coin
+        ("1CS8g7nwaxPPprb4vqcTVdLCuCRirsbsMb", Some(CoinProfile::BITCOIN)),
+        ("D6yCpZKjdfqdpFgoYGVZHbGnkRcS9Prcjs", Some(CoinProfile::DOGECOIN)),
+        ("3KKCYKKJjFtxQa4nPSnk2g8EXPo62r3Pzr", Some(CoinProfile::BITCOIN)),
+        ("bc1qzsg4tjzm3uljuhn82hxe92rhfmwg94qm0cakyz", Some(CoinProfile::BITCOIN)),
+        ("0x09332B1E45e6172fB26E46B3DB4411201547560a", None)
+    ];
+    for tuple in vectors {
+        println!("Testing coin of [{}]", tuple.0);
+        assert_eq!(tuple.1, tuple.0.parse::<Target>().unwrap().coin());
+    }
+    let testnet: Target = "mhM4aMU59HNc4MypXFUNZkKWjHUqnce7ZM".parse().unwrap();
+    assert_eq!(0xef, testnet.coin().unwrap().wif_version);
+}
@@ -0,0 +1,306 @@
+// Word level templates, a better fit for warp passphrases than character masks:
+//      {adjective} {noun} {year:1990-2020}{sym?}
+// Text outside braces is literal, {{ and }} are literal braces. Inside braces:
+//      {name}          every word of the wordlist called name
+//      {name:A-B}      every number from A to B, zero padded to the width of A if A has a leading 0,
+//                      so {pin:0000-9999}; the name only documents the slot
+//      {a|b|c}         one of these, none of them empty
+//      {...?}          any of the above, or nothing
+// A template is enumerated like a mask, the last slot turning fastest, an optional slot trying
+// nothing first, so candidate n is always the same phrase. When every slot is optional the phrase
+// of nothing at all is left out. Optional slots that can render the same text make duplicates,
+// {noun?}{noun?} tries each noun twice, and the keyspace counts every one of them.
+
+use error::WarpError;
+use keyspace::{self, Keyspace};
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Values {
+    Words(Vec<Vec<u8>>),
+    Range { start: u64, count: u128, width: usize }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Slot {
+    values: Values,
+    optional: bool
+}
+
+impl Slot {
+    fn literal(text: Vec<u8>) -> Slot {
+        Slot { values: Values::Words(vec![text]), optional: false }
+    }
+
+    fn len(&self) -> u128 {
+        let values = match self.values {
+            Values::Words(ref words) => words.len() as u128,
+            Values::Range { count, .. } => count
+        };
+        values + self.optional as u128
+    }
+
+    fn render(&self, mut index: u128, phrase: &mut Vec<u8>) {
+        if self.optional {
+            if index == 0 {
+                return;
+            }
+            index -= 1;
+        }
+        match self.values {
+            Values::Words(ref words) => phrase.extend_from_slice(&words[index as usize]),
+            Values::Range { start, width, .. } => {
+                phrase.extend_from_slice(format!("{:0width$}", start as u128 + index, width = width).as_bytes())
+            }
+        }
+    }
+}
+
+/**
+ * The values of one {...} slot, without its braces and ? mark.
+ */
+fn parse_slot(slot: &str, lists: &HashMap<String, Vec<Vec<u8>>>) -> Result<Values, WarpError> {
+    if slot.contains('|') {
+        let mut words: Vec<Vec<u8>> = vec![];
+        for word in slot.split('|').map(|word| word.as_bytes().to_vec()) {
+            if word.is_empty() {
+                return Err(WarpError::InvalidPattern(format!("{{{}}} has an empty choice, mark the slot optional with ? instead", slot)));
+            }
+            if !words.contains(&word) {
+                words.push(word);
+            }
+        }
+        return Ok(Values::Words(words));
+    }
+    if let Some(colon) = slot.find(':') {
+        let range = &slot[colon + 1..];
+        let invalid = || WarpError::InvalidPattern(format!("{{{}}} is not a range like 1990-2020", slot));
+        let dash = range.find('-').ok_or_else(invalid)?;
+        let (first, last) = (&range[..dash], &range[dash + 1..]);
+        let start: u64 = first.parse().map_err(|_| invalid())?;
+        let end: u64 = last.parse().map_err(|_| invalid())?;
+        if start > end {
+            return Err(invalid());
+        }
+        let width = if first.len() > 1 && first.starts_with('0') { first.len() } else { 0 };
+        return Ok(Values::Range { start, count: (end - start) as u128 + 1, width });
+    }
+    if slot.is_empty() {
+        return Err(WarpError::InvalidPattern("{} names no wordlist".to_owned()));
+    }
+    match lists.get(slot) {
+        Some(words) if words.is_empty() => Err(WarpError::InvalidPattern(format!("the {} wordlist is empty", slot))),
+        Some(words) => {
+            let mut distinct: Vec<Vec<u8>> = vec![];
+            for word in words {
+                if !distinct.contains(word) {
+                    distinct.push(word.clone());
+                }
+            }
+            Ok(Values::Words(distinct))
+        },
+        None => Err(WarpError::InvalidPattern(format!("no wordlist called {}", slot)))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+    /// Literal text and slots in order, literal text being a slot of one value.
+    slots: Vec<Slot>,
+    /// Whether every slot is optional, so the first combination is the empty phrase and is skipped.
+    all_optional: bool
+}
+
+impl Template {
+    /**
+     * Parses a template, {name} slots take their words from `lists`.
+     */
+    pub fn parse(template: &str, lists: &HashMap<String, Vec<Vec<u8>>>) -> Result<Template, WarpError> {
+        let mut slots = vec![];
+        let mut literal: Vec<u8> = vec![];
+        let mut rest = template;
+        while !rest.is_empty() {
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                literal.push(rest.as_bytes()[0]);
+                rest = &rest[2..];
+            } else if rest.starts_with('}') {
+                return Err(WarpError::InvalidPattern(format!("unopened }} in {}", template)));
+            } else if let Some(inner) = rest.strip_prefix('{') {
+                let close = inner.find('}')
+                    .ok_or_else(|| WarpError::InvalidPattern(format!("unclosed {{ in {}", template)))?;
+                let slot = &inner[..close];
+                let (slot, optional) = match slot.strip_suffix('?') {
+                    Some(slot) => (slot, true),
+                    None => (slot, false)
+                };
+                if !literal.is_empty() {
+                    slots.push(Slot::literal(literal.split_off(0)));
+                }
+                slots.push(Slot { values: parse_slot(slot, lists)?, optional });
+                rest = &inner[close + 1..];
+            } else {
+                let next = rest.find(['{', '}']).unwrap_or(rest.len());
+                literal.extend_from_slice(&rest.as_bytes()[..next]);
+                rest = &rest[next..];
+            }
+        }
+        if !literal.is_empty() {
+            slots.push(Slot::literal(literal));
+        }
+        if slots.is_empty() {
+            return Err(WarpError::InvalidPattern("the template is empty".to_owned()));
+        }
+        slots.iter().try_fold(1u128, |total, slot| total.checked_mul(slot.len()))
+            .ok_or_else(keyspace::overflow)?;
+        let all_optional = slots.iter().all(|slot| slot.optional);
+        Ok(Template { slots, all_optional })
+    }
+
+    fn digits(&self, mut index: u128) -> Option<Vec<u128>> {
+        if index >= self.keyspace() {
+            return None;
+        }
+        index += self.all_optional as u128;
+        let mut digits = vec![0; self.slots.len()];
+        for position in (0..self.slots.len()).rev() {
+            let radix = self.slots[position].len();
+            digits[position] = index % radix;
+            index /= radix;
+        }
+        Some(digits)
+    }
+
+    fn render(&self, digits: &[u128]) -> Vec<u8> {
+        let mut phrase = vec![];
+        for (slot, digit) in self.slots.iter().zip(digits) {
+            slot.render(*digit, &mut phrase);
+        }
+        phrase
+    }
+}
+
+impl Keyspace for Template {
+    /**
+     * Duplicates of overlapping optional slots included.
+     */
+    fn keyspace(&self) -> u128 {
+        self.slots.iter().map(Slot::len).product::<u128>() - self.all_optional as u128
+    }
+
+    fn candidate(&self, index: u128) -> Option<Vec<u8>> {
+        self.digits(index).map(|digits| self.render(&digits))
+    }
+
+    fn iter_from(&self, start: u128) -> Box<dyn Iterator<Item = Vec<u8>> + '_> {
+        Box::new(TemplateIter { template: self, digits: self.digits(start) })
+    }
+}
+
+/// Walks a template's keyspace without recomputing each candidate from its index.
+struct TemplateIter<'a> {
+    template: &'a Template,
+    digits: Option<Vec<u128>>
+}
+
+impl<'a> Iterator for TemplateIter<'a> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let template = self.template;
+        let digits = self.digits.as_mut()?;
+        let candidate = template.render(digits);
+        let mut position = digits.len();
+        loop {
+            if position == 0 {
+                self.digits = None;
+                break;
+            }
+            position -= 1;
+            digits[position] += 1;
+            if digits[position] < template.slots[position].len() {
+                break;
+            }
+            digits[position] = 0;
+        }
+        Some(candidate)
+    }
+}
+
+#[cfg(test)]
+fn test_lists() -> HashMap<String, Vec<Vec<u8>>> {
+    let mut lists = HashMap::new();
+    lists.insert("adjective".to_owned(), vec![b"big".to_vec(), b"red".to_vec(), b"big".to_vec()]);
+    lists.insert("noun".to_owned(), vec![b"cow".to_vec()]);
+    lists.insert("sym".to_owned(), vec![b"!".to_vec(), b"?".to_vec()]);
+    lists.insert("none".to_owned(), vec![]);
+    lists
+}
+
+#[test]
+fn test_template_keyspace() {
+    let vectors: Vec<(&str, u128, &str, &str)> = vec![
+        // Template, keyspace, first candidate, last candidate
+        ("{adjective} {noun} {year:1990-2020}{sym?}", 2 * 31 * 3, "big cow 1990", "red cow 2020?"),
+        ("{pin:0000-9999}", 10_000, "0000", "9999"),
+        ("{n:7-12}", 6, "7", "12"),
+        ("{Dutch|Power|Cows} {n:1-3?}", 3 * 4, "Dutch ", "Cows 3"),
+        ("{{literal}} {noun}", 1, "{literal} cow", "{literal} cow"),
+        ("warp", 1, "warp", "warp"),
+        ("{noun?}{noun?}", 3, "cow", "cowcow"),
+        ("{sym?}", 2, "!", "?")
+    ];
+    for tuple in vectors {
+        println!("Testing template [{}]", tuple.0);
+        let template = Template::parse(tuple.0, &test_lists()).unwrap();
+        assert_eq!(tuple.1, template.keyspace());
+        let candidates = keyspace::assert_numbering(&template);
+        assert_eq!(tuple.2.as_bytes(), &candidates[0][..]);
+        assert_eq!(tuple.3.as_bytes(), &candidates[candidates.len() - 1][..]);
+    }
+}
+
+#[test]
+fn test_template_seek() {
+    let template = Template::parse("{adjective} {noun} {year:1990-1991}{sym?}", &test_lists()).unwrap();
+    let candidates = keyspace::as_strings(keyspace::assert_numbering(&template));
+    assert_eq!(vec![
+        "big cow 1990", "big cow 1990!", "big cow 1990?", "big cow 1991", "big cow 1991!", "big cow 1991?",
+        "red cow 1990", "red cow 1990!", "red cow 1990?", "red cow 1991", "red cow 1991!", "red cow 1991?"
+    ], candidates);
+
+    let wide = Template::parse("{n:0-18446744073709551615}", &test_lists()).unwrap();
+    assert_eq!(1 << 64, wide.keyspace());
+    assert_eq!(Some(b"18446744073709551615".to_vec()), wide.candidate((1 << 64) - 1));
+}
+
+#[test]
+fn test_invalid_templates() {
+    let vectors: Vec<&str> = vec![
+        "",
+        "{adjective",
+        "noun}",
+        "{}",
+        "{?}",
+        "{verb}",
+        "{none}",
+        "{year:2020-1990}",
+        "{year:1990}",
+        "{year:a-b}",
+        "{a|}",
+        "{a||b}",
+        "{n:0-18446744073709551615}{n:0-18446744073709551615}{n:0-9}"
+    ];
+    for template in vectors {
+        println!("Testing invalid template [{}]", template);
+        assert!(Template::parse(template, &test_lists()).is_err());
+    }
+}
+
+#[test]
+fn test_template_optional_slots() {
+    let template = Template::parse("{noun?}{sym?}", &test_lists()).unwrap();
+    assert_eq!(vec!["!", "?", "cow", "cow!", "cow?"], keyspace::as_strings(keyspace::assert_numbering(&template)));
+
+    let duplicates = Template::parse("{noun?}{noun?}", &test_lists()).unwrap();
+    assert_eq!(vec![b"cow".to_vec(), b"cow".to_vec(), b"cowcow".to_vec()], duplicates.iter().collect::<Vec<Vec<u8>>>());
+}
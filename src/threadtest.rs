@@ -6,7 +6,10 @@ use bitcoin;
 use xor;
 
 use warpwallet;
-use warpwallet::add_byte_to_string;
+use network::Network;
+use params::WarpParams;
+use targets::TargetSet;
+use warpwallet::{DerivedKeypair, WarpWallet};
 
 fn thread_test_simple() {
     let (tx, rx): (mpsc::Sender<Vec<u8>>, mpsc::Receiver<Vec<u8>>) = mpsc::channel();
@@ -20,12 +23,12 @@ fn thread_test_simple() {
     let salt = "a@b.c";
 
     thread::spawn(move || {
-        let s1 = warpwallet::perform_warp_scrypt(&pass_phrase, salt);
+        let s1 = warpwallet::perform_warp_scrypt(&pass_phrase, salt, &WarpParams::keybase_v1()).unwrap();
         tx.send(s1);
     });
 
     thread::spawn(move || {
-        let s2 = warpwallet::perform_warp_pbkdf2(&pass_phrase_2, salt);
+        let s2 = warpwallet::perform_warp_pbkdf2(&pass_phrase_2, salt, &WarpParams::keybase_v1()).unwrap();
         tx2.send(s2);
     });
 
@@ -38,8 +41,8 @@ fn thread_test_simple() {
     });
 
     let s3 = s3rx.recv().unwrap();
-    let wif = bitcoin::secret_exponent_to_private_key(s3, false);
-    let address = bitcoin::private_key_wif_to_public_address(&wif);
+    let wif = bitcoin::secret_exponent_to_private_key(s3, false, Network::Bitcoin).unwrap();
+    let address = bitcoin::private_key_wif_to_public_address(&wif).unwrap();
     println!("Result: {:?}", address);
 }
 
@@ -70,11 +73,13 @@ fn speed_test_serial() {
     let start = PreciseTime::now();
 
     const SALT: [u8;5] = [97, 64, 98, 46, 99]; // a@b.c
+    let targets = TargetSet::from_addresses(vec!["1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]).unwrap();
 
     for index in 0..iterations {
-        let result = warpwallet::print_phrase_wif_address_warp_wallet(generate_random_bytes(8), SALT.to_vec());
-        println!("phrase [{}], wif [{}], addr [{}]", result[0], result[1], result[2]);
-        warpwallet::print_if_address_matches(&result[2]);
+        let phrase = generate_random_bytes(8);
+        let keypair = WarpWallet::from_bytes(phrase.clone(), SALT.to_vec()).derive().unwrap();
+        println!("phrase [{}], wif [{}], addr [{}]", String::from_utf8(phrase).unwrap(), keypair.uncompressed.wif, keypair.uncompressed.address);
+        assert!(targets.matches(&keypair).unwrap().is_empty());
     }
 
     let end = PreciseTime::now();
@@ -88,21 +93,24 @@ fn speed_test_parallel() {
     let start = PreciseTime::now();
     let iterations = 12;
 
-    let (tx, rx): (mpsc::Sender<Vec<String>>, mpsc::Receiver<Vec<String>>) = mpsc::channel();
+    let (tx, rx): (mpsc::Sender<(String, DerivedKeypair)>, mpsc::Receiver<(String, DerivedKeypair)>) = mpsc::channel();
 
     const SALT: [u8;5] = [97, 64, 98, 46, 99]; // a@b.c
+    let targets = TargetSet::from_addresses(vec!["1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA"]).unwrap();
 
     for _inner_index in 0..iterations {
         let cloned_tx = tx.clone();
         thread::spawn(move || {
-            cloned_tx.send(warpwallet::print_phrase_wif_address_warp_wallet(generate_random_string(8).as_bytes().to_vec(), SALT.to_vec()));
+            let phrase = generate_random_string(8);
+            let keypair = WarpWallet::from_bytes(phrase.as_bytes().to_vec(), SALT.to_vec()).derive().unwrap();
+            cloned_tx.send((phrase, keypair));
         });
     }
 
     for index in 0..iterations {
-        let result = rx.recv().unwrap();
-        println!("phrase [{}], wif [{}], addr [{}]", result[0], result[1], result[2]);
-        warpwallet::print_if_address_matches(&result[2]);
+        let (phrase, keypair) = rx.recv().unwrap();
+        println!("phrase [{}], wif [{}], addr [{}]", phrase, keypair.uncompressed.wif, keypair.uncompressed.address);
+        assert!(targets.matches(&keypair).unwrap().is_empty());
     }
 
     let end = PreciseTime::now();
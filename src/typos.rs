@@ -0,0 +1,231 @@
+// Candidates around a passphrase its owner is almost sure of, closest and likeliest first.
+// Distance 1 is one slip: caps lock left on, two characters swapped, a character dropped or doubled,
+// a space missing or added, or a neighbouring key on a US keyboard hit instead. Distance 2 is two of
+// them. Each slip has a rough weight, a candidate of two slips weighs their product, and every
+// distance is sorted by weight before the next one starts. Phrases that more than one way of slipping
+// makes are tried once, at their closest distance and highest weight.
+
+use error::WarpError;
+#[cfg(test)]
+use keyspace;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+pub const MAX_DISTANCE: usize = 2;
+
+// Rough relative likelihood of one slip at one place. Only their order really matters.
+const CAPS_LOCK: f64 = 0.05;
+const TRANSPOSITION: f64 = 0.02;
+const MISSING_SPACE: f64 = 0.02;
+const DROPPED: f64 = 0.01;
+const DOUBLED: f64 = 0.01;
+const EXTRA_SPACE: f64 = 0.005;
+const ADJACENT_KEY: f64 = 0.004;
+
+// US keyboard rows, unshifted and shifted. Each row is offset so that a key touches the keys at the
+// same and the next column of the row above, and at the same and the previous column of the row below.
+const ROWS: [[&[u8]; 4]; 2] = [
+    [b"`1234567890-=", b" qwertyuiop[]\\", b" asdfghjkl;'", b" zxcvbnm,./"],
+    [b"~!@#$%^&*()_+", b" QWERTYUIOP{}|", b" ASDFGHJKL:\"", b" ZXCVBNM<>?"]
+];
+
+/**
+ * The keys around `key` on the same layer of the keyboard, none if it is not on the keyboard.
+ */
+fn neighbours(key: u8) -> Vec<u8> {
+    let key_at = |rows: &[&[u8]; 4], row: isize, column: isize| -> Option<u8> {
+        if row < 0 || column < 0 {
+            return None;
+        }
+        rows.get(row as usize)
+            .and_then(|keys| keys.get(column as usize))
+            .cloned()
+            .filter(|key| *key != b' ')
+    };
+    for rows in &ROWS {
+        for (row, keys) in rows.iter().enumerate() {
+            if let Some(column) = keys.iter().position(|candidate| *candidate == key && key != b' ') {
+                let (row, column) = (row as isize, column as isize);
+                let around = [(row, column - 1), (row, column + 1), (row - 1, column), (row - 1, column + 1), (row + 1, column - 1), (row + 1, column)];
+                return around.iter().filter_map(|&(row, column)| key_at(rows, row, column)).collect();
+            }
+        }
+    }
+    vec![]
+}
+
+/**
+ * Every phrase one slip away from `phrase`, with the weight of that slip, in no particular order.
+ */
+fn edits(phrase: &[u8]) -> Vec<(Vec<u8>, f64)> {
+    let mut edits = vec![];
+    if phrase.iter().any(u8::is_ascii_alphabetic) {
+        let inverted = phrase.iter().map(|byte| match *byte {
+            b'a'..=b'z' => byte.to_ascii_uppercase(),
+            b'A'..=b'Z' => byte.to_ascii_lowercase(),
+            other => other
+        }).collect();
+        edits.push((inverted, CAPS_LOCK));
+    }
+    for index in 1..phrase.len() {
+        if phrase[index - 1] != phrase[index] {
+            let mut swapped = phrase.to_vec();
+            swapped.swap(index - 1, index);
+            edits.push((swapped, TRANSPOSITION));
+        }
+    }
+    for index in 0..phrase.len() {
+        let mut dropped = phrase.to_vec();
+        let byte = dropped.remove(index);
+        edits.push((dropped, if byte == b' ' { MISSING_SPACE } else { DROPPED }));
+        let mut doubled = phrase.to_vec();
+        doubled.insert(index, byte);
+        edits.push((doubled, if byte == b' ' { EXTRA_SPACE } else { DOUBLED }));
+    }
+    for index in 0..=phrase.len() {
+        let mut spaced = phrase.to_vec();
+        spaced.insert(index, b' ');
+        edits.push((spaced, EXTRA_SPACE));
+    }
+    for (index, byte) in phrase.iter().enumerate() {
+        for neighbour in neighbours(*byte) {
+            let mut replaced = phrase.to_vec();
+            replaced[index] = neighbour;
+            edits.push((replaced, ADJACENT_KEY));
+        }
+    }
+    edits
+}
+
+/// Streams the typo candidates of one phrase, one distance at a time.
+pub struct Typos {
+    max_distance: usize,
+    distance: usize,
+    /// The previous distance with weights, the next one is made of it.
+    previous: Vec<(Vec<u8>, f64)>,
+    pending: VecDeque<Vec<u8>>,
+    /// Everything tried so far, so a later distance does not repeat it.
+    seen: HashSet<Vec<u8>>
+}
+
+impl Typos {
+    /**
+     * The phrase itself first, then every candidate up to `max_distance` slips away, 1 or 2.
+     */
+    pub fn new(phrase: &[u8], max_distance: usize) -> Result<Typos, WarpError> {
+        if max_distance == 0 || max_distance > MAX_DISTANCE {
+            return Err(WarpError::InvalidPattern(format!("typos are searched 1 to {} slips away", MAX_DISTANCE)));
+        }
+        if phrase.is_empty() {
+            return Err(WarpError::InvalidPattern("there are no typos of an empty phrase".to_owned()));
+        }
+        let mut seen = HashSet::new();
+        seen.insert(phrase.to_vec());
+        Ok(Typos {
+            max_distance,
+            distance: 0,
+            previous: vec![(phrase.to_vec(), 1.0)],
+            pending: vec![phrase.to_vec()].into_iter().collect(),
+            seen
+        })
+    }
+
+    /**
+     * Works out the next distance, its candidates sorted by weight, first found first on a tie.
+     */
+    fn next_distance(&mut self) {
+        self.distance += 1;
+        let mut weights: Vec<(Vec<u8>, f64)> = vec![];
+        let mut positions: HashMap<Vec<u8>, usize> = HashMap::new();
+        for (phrase, weight) in &self.previous {
+            for (candidate, slip) in edits(phrase) {
+                if self.seen.contains(&candidate) {
+                    continue;
+                }
+                let weight = weight * slip;
+                match positions.get(&candidate) {
+                    Some(&position) => if weights[position].1 < weight {
+                        weights[position].1 = weight;
+                    },
+                    None => {
+                        positions.insert(candidate.clone(), weights.len());
+                        weights.push((candidate, weight));
+                    }
+                }
+            }
+        }
+        weights.sort_by(|a, b| b.1.partial_cmp(&a.1).expect("weights are never NaN"));
+        for (candidate, _) in &weights {
+            self.seen.insert(candidate.clone());
+            self.pending.push_back(candidate.clone());
+        }
+        self.previous = weights;
+    }
+}
+
+impl Iterator for Typos {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        while self.pending.is_empty() && self.distance < self.max_distance {
+            self.next_distance();
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[test]
+fn test_keyboard_neighbours() {
+    let vectors: Vec<(u8, &[u8])> = vec![
+        // Key, neighbours
+        (b'q', b"w12a"),
+        (b'g', b"fhtyvb"),
+        (b'a', b"sqwz"),
+        (b'm', b"n,jk"),
+        (b'1', b"`2q"),
+        (b'G', b"FHTYVB"),
+        (b'!', b"~@Q"),
+        (b' ', b""),
+        (0xe9, b"")
+    ];
+    for tuple in vectors {
+        println!("Testing neighbours of [{}]", tuple.0 as char);
+        assert_eq!(tuple.1.to_vec(), neighbours(tuple.0));
+    }
+}
+
+#[test]
+fn test_typos_order() {
+    let typos = keyspace::as_strings(Typos::new(b"Cow", 1).unwrap());
+    assert_eq!(vec!["Cow", "cOW", "oCw", "Cwo", "ow", "CCow", "Cw", "Coow", "Co", "Coww"], typos[..10].to_vec());
+    assert!(typos.contains(&" Cow".to_owned()));
+    assert!(typos.contains(&"Xow".to_owned()));
+    assert!(typos.contains(&"Cpw".to_owned()));
+    assert!(!typos.contains(&"Cxw".to_owned()));
+
+    keyspace::assert_distinct(&typos);
+
+    // A dropped space weighs more than a dropped letter.
+    let typos: Vec<Vec<u8>> = Typos::new(b"a cow", 1).unwrap().collect();
+    let position = |typo: &[u8]| typos.iter().position(|candidate| candidate[..] == typo[..]).unwrap();
+    assert!(position(b"acow") < position(b"a cw"));
+    assert!(position(b"a cw") < position(b"a cpw"));
+}
+
+#[test]
+fn test_typos_distance_two() {
+    let first: Vec<Vec<u8>> = Typos::new(b"Dutch Power Cows", 1).unwrap().collect();
+    let both: Vec<Vec<u8>> = Typos::new(b"Dutch Power Cows", 2).unwrap().collect();
+    assert_eq!(first[..], both[..first.len()]);
+    assert!(both.len() > first.len() * 10);
+    let position = |typo: &[u8]| both.iter().position(|candidate| candidate[..] == typo[..]);
+    // Caps lock and a missing space, then two swapped pairs.
+    assert!(position(b"dUTCHpOWER cOWS").unwrap() < position(b"uDtch Power Cosw").unwrap());
+    assert!(position(b"Dutch Power Cows").unwrap() == 0);
+    assert_eq!(None, position(b"Dutch Power"));
+
+    keyspace::assert_distinct(&both);
+
+    assert!(Typos::new(b"cow", 0).is_err());
+    assert!(Typos::new(b"cow", 3).is_err());
+    assert!(Typos::new(b"", 1).is_err());
+}
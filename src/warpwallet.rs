@@ -3,85 +3,238 @@ extern crate hex;
 extern crate xor;
 
 use bitcoin::{secret_exponent_to_private_key, private_key_wif_to_public_address};
+use bitcoin::{Address, KeyFormat, PrivateKeyWif, PublicKey, SecretExponent};
+use error::WarpError;
+use ethereum::EthereumAddress;
+use coins::CoinProfile;
+use network::Network;
+use params::{WarpParams, WARP_KEY_LENGTH};
 use self::bytes::{BytesMut, BufMut};
 use pbkdf2::perform_pbkdf2;
 use scrypt::perform_scrypt;
+use std::str;
 use std::thread;
 use xor::xor;
 
 use std::sync::mpsc;
 
-const WARP_PBKDF2_CONCAT: u8 = 2u8;
-// 2^16
-const WARP_PBKDF2_ITERATIONS: u32 = 65_536;
-const WARP_KEY_LENGTH: usize = 32;
-// 2^18
-const WARP_SCRYPT_ITERATIONS: u32 = 262_144;
-const WARP_SCRYPT_CONCAT: u8 = 1u8;
-const WARP_SCRYPT_MEM_DIFF: u32 = 8;
-const WARP_SCRYPT_PAR_DIFF: u32 = 1;
-
-fn concat_arrays(a: Vec<u8>, b: u8) -> Vec<u8> {
+fn concat_arrays(a: Vec<u8>, b: u8) -> Result<Vec<u8>, WarpError> {
     thread::spawn(move || {
         let mut output_array = BytesMut::with_capacity(a.len() + 1);
         output_array.put(a);
         output_array.put(b);
         output_array.to_vec()
-    }).join().unwrap()
+    }).join().map_err(WarpError::from)
 }
 
 /**
  * Returns the secret exponent.
  */
-fn warp_wallet_bytes(phrase_bytes: Vec<u8>, salt_bytes: Vec<u8>) -> Vec<u8> {
+pub fn warp_wallet_bytes(phrase_bytes: Vec<u8>, salt_bytes: Vec<u8>, params: &WarpParams) -> Result<Vec<u8>, WarpError> {
 
+    let params = *params;
     let cloned_phrase_bytes = phrase_bytes.clone();
     let cloned_salt_bytes = salt_bytes.clone();
 
-    let scrypt_phrase_handle = thread::spawn(move|| {concat_arrays(cloned_phrase_bytes, WARP_SCRYPT_CONCAT)});
-    let scrypt_salt_handle = thread::spawn(move|| {concat_arrays(cloned_salt_bytes, WARP_SCRYPT_CONCAT)});
+    let scrypt_phrase_handle = thread::spawn(move|| {concat_arrays(cloned_phrase_bytes, params.scrypt_concat)});
+    let scrypt_salt_handle = thread::spawn(move|| {concat_arrays(cloned_salt_bytes, params.scrypt_concat)});
 
-    let pbkdf2_phrase_handle = thread::spawn(move|| {concat_arrays(phrase_bytes, WARP_PBKDF2_CONCAT)});
-    let pbkdf2_salt_handle = thread::spawn(move|| {concat_arrays(salt_bytes, WARP_PBKDF2_CONCAT)});
+    let pbkdf2_phrase_handle = thread::spawn(move|| {concat_arrays(phrase_bytes, params.pbkdf2_concat)});
+    let pbkdf2_salt_handle = thread::spawn(move|| {concat_arrays(salt_bytes, params.pbkdf2_concat)});
 
-    let scrypt_phrase = scrypt_phrase_handle.join().unwrap();
-    let scrypt_salt = scrypt_salt_handle.join().unwrap();
+    let scrypt_phrase = scrypt_phrase_handle.join()??;
+    let scrypt_salt = scrypt_salt_handle.join()??;
 
-    let pbkdf2_phrase = pbkdf2_phrase_handle.join().unwrap();
-    let pbkdf2_salt = pbkdf2_salt_handle.join().unwrap();
+    let pbkdf2_phrase = pbkdf2_phrase_handle.join()??;
+    let pbkdf2_salt = pbkdf2_salt_handle.join()??;
 
-    let (tx, rx): (mpsc::Sender<Vec<u8>>, mpsc::Receiver<Vec<u8>>) = mpsc::channel();
+    type SeedResult = Result<Vec<u8>, WarpError>;
+    let (tx, rx): (mpsc::Sender<SeedResult>, mpsc::Receiver<SeedResult>) = mpsc::channel();
     let s2_tx = tx.clone();
 
+    // A send only fails when this function already returned on the other seed's error.
     thread::spawn(move || {
-        tx.send( perform_scrypt(
+        let _ = tx.send( perform_scrypt(
             scrypt_phrase,
             scrypt_salt,
-            WARP_SCRYPT_ITERATIONS,
-            WARP_SCRYPT_MEM_DIFF,
-            WARP_SCRYPT_PAR_DIFF,
+            params.scrypt_iterations,
+            params.scrypt_mem_diff,
+            params.scrypt_par_diff,
             WARP_KEY_LENGTH
         ));
     });
     thread::spawn(move || {
-        s2_tx.send(perform_pbkdf2(
+        let _ = s2_tx.send(perform_pbkdf2(
             pbkdf2_phrase,
             pbkdf2_salt,
-            WARP_PBKDF2_ITERATIONS,
+            params.pbkdf2_iterations,
             WARP_KEY_LENGTH
         ));
     });
 
-    let s1: Vec<u8> = rx.recv().unwrap();
-    let s2: Vec<u8> = rx.recv().unwrap();
-    xor(&s1, &s2)
+    let s1: Vec<u8> = rx.recv()??;
+    let s2: Vec<u8> = rx.recv()??;
+    Ok(xor(&s1, &s2))
 }
 
-pub fn warp_wallet(pass_phrase: &str, salt: &str) -> Vec<u8> {
+pub fn warp_wallet(pass_phrase: &str, salt: &str, params: &WarpParams) -> Result<Vec<u8>, WarpError> {
     let phrase_bytes = pass_phrase.as_bytes().to_vec();
     let salt_bytes = salt.as_bytes().to_vec();
 
-    warp_wallet_bytes(phrase_bytes, salt_bytes)
+    warp_wallet_bytes(phrase_bytes, salt_bytes, params)
+}
+
+/// A passphrase and salt pair to derive a warp wallet from.
+#[derive(Clone, Debug)]
+pub struct WarpWallet {
+    passphrase: Vec<u8>,
+    salt: Vec<u8>,
+    params: WarpParams,
+    coin: CoinProfile
+}
+
+/// The WIF, public key and P2PKH address of a secret exponent in one key format, for one coin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedKey {
+    pub format: KeyFormat,
+    pub wif: PrivateKeyWif,
+    pub public_key: PublicKey,
+    pub address: Address
+}
+
+impl EncodedKey {
+    pub fn from_exponent<C: Into<CoinProfile>>(exponent: &SecretExponent, format: KeyFormat, coin: C) -> Result<EncodedKey, WarpError> {
+        let coin = coin.into();
+        let public_key = exponent.to_public_key(format.is_compressed())?;
+        Ok(EncodedKey {
+            format,
+            wif: exponent.to_wif(format.is_compressed(), coin)?,
+            address: public_key.to_p2pkh_address(coin),
+            public_key
+        })
+    }
+}
+
+/// Everything derived from a single warp passphrase and salt.
+/// keybase.io/warp shows the uncompressed key, most modern wallets import the compressed one.
+/// The segwit addresses are None for coins without segwit, like Dogecoin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DerivedKeypair {
+    pub coin: CoinProfile,
+    pub secret_exponent: SecretExponent,
+    pub uncompressed: EncodedKey,
+    pub compressed: EncodedKey,
+    /// Native segwit (bc1q...) address of the compressed key.
+    pub p2wpkh: Option<Address>,
+    /// Nested segwit (3...) address of the compressed key.
+    pub p2sh_p2wpkh: Option<Address>,
+    /// Key path only taproot (bc1p...) address of the compressed key.
+    pub p2tr: Option<Address>,
+    /// The same exponent as an Ethereum key, whatever the coin.
+    pub ethereum: EthereumAddress
+}
+
+impl DerivedKeypair {
+    pub fn from_exponent<C: Into<CoinProfile>>(exponent: SecretExponent, coin: C) -> Result<DerivedKeypair, WarpError> {
+        let coin = coin.into();
+        let compressed = EncodedKey::from_exponent(&exponent, KeyFormat::Compressed, coin)?;
+        let has_segwit = coin.bech32_hrp.is_some();
+        Ok(DerivedKeypair {
+            coin,
+            uncompressed: EncodedKey::from_exponent(&exponent, KeyFormat::Uncompressed, coin)?,
+            p2wpkh: if has_segwit { Some(compressed.public_key.to_p2wpkh_address(coin)?) } else { None },
+            p2sh_p2wpkh: if has_segwit { Some(compressed.public_key.to_p2sh_p2wpkh_address(coin)?) } else { None },
+            p2tr: if has_segwit { Some(compressed.public_key.to_p2tr_address(coin)?) } else { None },
+            ethereum: EthereumAddress::from_exponent(&exponent)?,
+            compressed,
+            secret_exponent: exponent
+        })
+    }
+
+    /**
+     * The segwit addresses this coin has, labelled by output type.
+     */
+    pub fn segwit_addresses(&self) -> Vec<(&'static str, &Address)> {
+        let labelled = vec![("p2wpkh", &self.p2wpkh), ("p2sh-p2wpkh", &self.p2sh_p2wpkh), ("p2tr", &self.p2tr)];
+        labelled.into_iter()
+            .filter_map(|(label, address)| address.as_ref().map(|address| (label, address)))
+            .collect()
+    }
+
+    pub fn key(&self, format: KeyFormat) -> &EncodedKey {
+        match format {
+            KeyFormat::Uncompressed => &self.uncompressed,
+            KeyFormat::Compressed => &self.compressed
+        }
+    }
+}
+
+impl WarpWallet {
+    pub fn new(pass_phrase: &str, salt: &str) -> WarpWallet {
+        WarpWallet::from_bytes(pass_phrase.as_bytes().to_vec(), salt.as_bytes().to_vec())
+    }
+
+    /**
+     * Uses the keybase v1 parameters on bitcoin mainnet, see with_params, with_network and with_coin
+     * for anything else.
+     */
+    pub fn from_bytes(phrase_bytes: Vec<u8>, salt_bytes: Vec<u8>) -> WarpWallet {
+        WarpWallet {
+            passphrase: phrase_bytes,
+            salt: salt_bytes,
+            params: WarpParams::keybase_v1(),
+            coin: CoinProfile::BITCOIN
+        }
+    }
+
+    pub fn with_params(mut self, params: WarpParams) -> WarpWallet {
+        self.params = params;
+        self
+    }
+
+    pub fn with_network(self, network: Network) -> WarpWallet {
+        self.with_coin(CoinProfile::from(network))
+    }
+
+    pub fn with_coin(mut self, coin: CoinProfile) -> WarpWallet {
+        self.coin = coin;
+        self
+    }
+
+    pub fn params(&self) -> &WarpParams {
+        &self.params
+    }
+
+    pub fn coin(&self) -> CoinProfile {
+        self.coin
+    }
+
+    pub fn passphrase(&self) -> &[u8] {
+        &self.passphrase
+    }
+
+    pub fn salt(&self) -> &[u8] {
+        &self.salt
+    }
+
+    pub fn passphrase_str(&self) -> Result<&str, WarpError> {
+        str::from_utf8(&self.passphrase).map_err(WarpError::from)
+    }
+
+    /**
+     * Runs scrypt and pbkdf2 and turns s1 ⊕ s2 into a bitcoin keypair.
+     * Fails with InvalidExponent in the (astronomically rare) case s1 ⊕ s2 is not a valid key.
+     */
+    pub fn derive(&self) -> Result<DerivedKeypair, WarpError> {
+        DerivedKeypair::from_exponent(self.derive_exponent()?, self.coin)
+    }
+
+    /**
+     * Only the secret exponent, skipping the WIF and address encoding derive does.
+     */
+    pub fn derive_exponent(&self) -> Result<SecretExponent, WarpError> {
+        SecretExponent::from_bytes(warp_wallet_bytes(self.passphrase.clone(), self.salt.clone(), &self.params)?)
+    }
 }
 
 #[test]
@@ -91,53 +244,194 @@ fn test_single_warp_wallet() {
     let pass_phrase = "Dutch Power Cows";
     let salt = "a@b.c";
 
-    let result = print_phrase_wif_address_warp_wallet(pass_phrase.as_bytes().to_vec(), salt.as_bytes().to_vec());
-    assert_eq!(pass_phrase, result[0]);
-    assert_eq!(expected_wif, result[1]);
-    assert_eq!(expected_address, result[2]);
+    let wallet = WarpWallet::new(pass_phrase, salt);
+    assert_eq!(Ok(pass_phrase), wallet.passphrase_str());
+    let keypair = wallet.derive().unwrap();
+    assert_eq!(expected_wif, keypair.uncompressed.wif.to_string());
+    assert_eq!(expected_address, keypair.uncompressed.address.to_string());
+    assert_eq!(keypair.uncompressed.public_key, keypair.secret_exponent.to_public_key(false).unwrap());
+    assert_eq!(&keypair.compressed, keypair.key(KeyFormat::Compressed));
+    assert_eq!(KeyFormat::Compressed, keypair.compressed.wif.format());
+    assert_eq!(33, keypair.compressed.public_key.as_bytes().len());
+    assert!(keypair.compressed.address != keypair.uncompressed.address);
+
+    let invalid_utf8 = WarpWallet::from_bytes(vec![0xff, 0xfe], salt.as_bytes().to_vec());
+    assert_eq!(Err(WarpError::NonUtf8Input), invalid_utf8.passphrase_str());
 }
 
-pub fn print_phrase_wif_address_warp_wallet(phrase_bytes: Vec<u8>, salt_bytes: Vec<u8>) -> Vec<String> {
-    let exp = warp_wallet_bytes(phrase_bytes.clone(), salt_bytes);
-    let wif = secret_exponent_to_private_key(exp, false);
-    let address = private_key_wif_to_public_address(&wif);
-    vec![ String::from_utf8(phrase_bytes).unwrap(), wif, address ]
+#[test]
+fn test_derived_keypair_from_exponent() {
+    let vectors = get_warp_wallet_vectors();
+    let compressed = vec![
+        // Compressed wif, compressed address, P2WPKH address of the first vectors
+        ("KzwmFdCY1PcZTaf13BYzTHfry6Sgk9mMcqQKrJ2iVkcmFwU5PyFq", "1AysGj1nKKxtJCHd3MYWtopgR6rvrv1rXJ", "bc1qd4ax8heq8k56ng9uh9egukuqucuardreuh5g5g"),
+        ("L4XUj5pQPnVpD4irHKtiGmJx44xn9M75HwnyGgKWT4N7tTj7m6ri", "16XCfsuWA1uBPMSXgmTGktP7cK4XirLCMG", "bc1q8j8dnqk3jr0hzq98rzed5gvs426wyvudys4k4a"),
+        ("KxotHVNoP8bYAEmzkcFGCXrpdmSbgixbfi5NwhbRSJcHaMNt1ydz", "1QDFnKCMrzeb4MGYG8Nx51J9jdKYuSamLb", "bc1ql6wrjtveeumzewnvkjatp5q7sawqvja0z9z4jp")
+    ];
+    for (vector, tuple) in vectors.iter().zip(compressed) {
+        println!("Testing exponent [{}]", vector.seeds[2]);
+        let keypair = DerivedKeypair::from_exponent(vector.seeds[2].parse().unwrap(), Network::Bitcoin).unwrap();
+        assert_eq!(vector.keys[0], keypair.uncompressed.wif.to_string());
+        assert_eq!(vector.keys[1], keypair.uncompressed.address.to_string());
+        assert_eq!(tuple.0, keypair.compressed.wif.to_string());
+        assert_eq!(tuple.1, keypair.compressed.address.to_string());
+        assert_eq!(tuple.1, keypair.key(KeyFormat::Compressed).address.to_string());
+        assert_eq!(tuple.2, keypair.p2wpkh.unwrap().to_string());
+    }
 }
 
-pub fn perform_warp_pbkdf2(pass_phrase: &str, salt: &str) -> Vec<u8> {
-    perform_pbkdf2(
-        add_byte_to_string(pass_phrase, WARP_PBKDF2_CONCAT),
-        add_byte_to_string(salt, WARP_PBKDF2_CONCAT),
-        WARP_PBKDF2_ITERATIONS,
-        WARP_KEY_LENGTH
-    )
+#[test]
+fn test_p2sh_p2wpkh_from_warp_vectors() {
+    let vectors = get_warp_wallet_vectors();
+    let expected = vec![
+        // P2SH-P2WPKH address of each warp vector, in the same order
+        "3R259dqQ1diXKQH7wGGCt9dqzdQs4xcnLh",
+        "38HJRFjLfm8sLJKSmtwWU6xruKicjtTeo9",
+        "39g2MnsRaG3T2aFA2KGJ6niy535BiCFiui",
+        "37WzD3xNFHbrEDnN5rZutggJM3osPHdqW8",
+        "3MqcMtbXGjFLCGZGi4nyefgTPDaLvYPVy4",
+        "3PaC67ke1RuyJ11xHFEp1UXEny7Fk4xMfA",
+        "3DmzY3qiyfPqb2Ndq3c4bf4WBbCkF461wG",
+        "3CMktmd7m1bYVK2a89ifKTZjStZiZd9d46",
+        "3FtfeeKvonsZ14ab7LiL4FND1GXZkJrTx8",
+        "3Mxg3ATjD237G2EnJJ3V5LS1CcgURdStHC",
+        "35Reme2Q1dFT7xWuiYXexB5KrQQgExVW9h",
+        "32UT9xatM1ayKAmBBdPLUC5E9UNinBvDt3"
+    ];
+    assert_eq!(vectors.len(), expected.len());
+    for (vector, address) in vectors.iter().zip(expected) {
+        println!("Testing phrase [{}] with salt [{}].", vector.passphrase, vector.salt);
+        let keypair = DerivedKeypair::from_exponent(vector.seeds[2].parse().unwrap(), Network::Bitcoin).unwrap();
+        assert_eq!(address, keypair.p2sh_p2wpkh.unwrap().to_string());
+    }
 }
 
-pub fn print_if_address_matches(input_string: &str) {
-    if "1MkupVKiCik9iyfnLrJoZLx9RH4rkF3hnA" == input_string {
-        println!("== ADDRESS FOUND == {}", input_string);
+#[test]
+fn test_p2tr_from_warp_vectors() {
+    let vectors = get_warp_wallet_vectors();
+    let expected = vec![
+        // BIP86 P2TR address of each warp vector, in the same order
+        "bc1pv7gepjnf08ftyk678gxq2cvshtk9jx835f7wt85n3mkrxxxcgkhsef3zgc",
+        "bc1pqrezdkua4v6wu2l5f26fzrpyxpts0zyrv6ewz5ph9xva9ymua0cszk2l5u",
+        "bc1pacu0svyfh3f2ut5l5l5dqpr70nz3ev3gdf7rcs3yk8met8ntt5mqe33age",
+        "bc1p98fpzmd9zsd7q80gtgye3p9848w9hg5lqj73c7ueppc99u9j6wzqm29pg5",
+        "bc1phkx79nngusgpyghxuxxlnngtfxyjhd7f6dxr67ndnc4jm6egny7sp5e722",
+        "bc1p427wpj5h7fhks9c0f28nxs59auf0l8l3frhcwzaczmdws372mfaslmpj5y",
+        "bc1pzm748xs30jnerwlmface9wnyfsvgl5qra6s9twjsmm4d0dy47pfq96y44u",
+        "bc1p9zx0yy942lmpsmvr6fcy52wfx95ygda72g2q8w3uahlrr4zqa2jscel8sr",
+        "bc1pfcvvtr7mux76g6dr93j5wxyzemjaxqlj3mtwu0c88qr953zct52qanytqr",
+        "bc1pcfesrkkwkfkvmesk4lyqkwannxl344freyhme2jk8kprfsxmzwts48r6d3",
+        "bc1p886pew7ywqk375mx3hktu5guqsl9nftpcljthmnmra7k2956kyyqvuquft",
+        "bc1p4ph9hrl6aya5hr4nc9lckln2ctvu0eds8r8dzsgj3ww6g9eemp4shnsqes"
+    ];
+    assert_eq!(vectors.len(), expected.len());
+    for (vector, address) in vectors.iter().zip(expected) {
+        println!("Testing phrase [{}] with salt [{}].", vector.passphrase, vector.salt);
+        let keypair = DerivedKeypair::from_exponent(vector.seeds[2].parse().unwrap(), Network::Bitcoin).unwrap();
+        assert_eq!(address, keypair.p2tr.unwrap().to_string());
     }
 }
 
-pub fn perform_warp_scrypt(pass_phrase: &str, salt: &str) -> Vec<u8> {
+#[test]
+fn test_ethereum_from_warp_vectors() {
+    let vectors = get_warp_wallet_vectors();
+    let expected = vec![
+        // EIP-55 address of each warp vector, in the same order
+        "0x76EFFb452CcD26605823C18632d2F2228aE60006",
+        "0xcb825fA5e35eBf2471f6fD3242cCCEcAF83452AE",
+        "0x3A0f2FF6e77a4578B982acD277Ce271bca6193ba",
+        "0x0dc0b92C8FeF18b1BA7c3a22C1FD68aE0Dd50dC3",
+        "0x868203E9751aad5e1D05C6f7aF45F2b85580Aa96",
+        "0x2ff4c91C36d59a3916E8E59Ee91377C569ab1e14",
+        "0x7C91Ed29676Fd5758102f42BBB01780ac74Ee526",
+        "0xC157E1d3C201b688aC81dcd9Da435D05cd65F4f9",
+        "0x520F6D688A59C7A46C97cb77F5b2a778393A5F3B",
+        "0x499AC2ABA0CBD07e33909173A102CF94648eD80d",
+        "0x191Badc5FD4dfE6224B6e65Ab06be285f9c8adA5",
+        "0x3e1DcdaDc2C8300212D8fA701A966B3219244Ff2"
+    ];
+    assert_eq!(vectors.len(), expected.len());
+    for (vector, address) in vectors.iter().zip(expected) {
+        println!("Testing phrase [{}] with salt [{}].", vector.passphrase, vector.salt);
+        let keypair = DerivedKeypair::from_exponent(vector.seeds[2].parse().unwrap(), CoinProfile::DOGECOIN).unwrap();
+        assert_eq!(address, keypair.ethereum.to_string());
+    }
+}
+
+#[test]
+fn test_reduced_cost_warp_wallet() {
+    let params = WarpParams {
+        scrypt_iterations: 1_024,
+        pbkdf2_iterations: 16,
+        ..WarpParams::keybase_v1()
+    };
+    let pass_phrase = "Dutch Power Cows";
+    let salt = "a@b.c";
+
+    let s1 = perform_scrypt(b"Dutch Power Cows\x01".to_vec(), b"a@b.c\x01".to_vec(), 1_024, 8, 1, 32).unwrap();
+    let s2 = perform_pbkdf2(b"Dutch Power Cows\x02".to_vec(), b"a@b.c\x02".to_vec(), 16, 32).unwrap();
+    assert_eq!(s1, perform_warp_scrypt(pass_phrase, salt, &params).unwrap());
+    assert_eq!(s2, perform_warp_pbkdf2(pass_phrase, salt, &params).unwrap());
+
+    let exponent = warp_wallet(pass_phrase, salt, &params).unwrap();
+    assert_eq!(xor(&s1, &s2), exponent);
+    assert!(exponent != warp_wallet(pass_phrase, salt, &WarpParams { pbkdf2_iterations: 17, ..params }).unwrap());
+
+    let keypair = WarpWallet::new(pass_phrase, salt).with_params(params).derive().unwrap();
+    assert_eq!(exponent.as_slice(), keypair.secret_exponent.as_bytes());
+    assert_eq!(Ok(keypair.secret_exponent.clone()), WarpWallet::new(pass_phrase, salt).with_params(params).derive_exponent());
+
+    let regtest = WarpWallet::new(pass_phrase, salt).with_params(params).with_network(Network::Regtest);
+    assert_eq!(CoinProfile::from(Network::Regtest), regtest.coin());
+    let regtest_keypair = regtest.derive().unwrap();
+    assert_eq!(keypair.secret_exponent, regtest_keypair.secret_exponent);
+    assert_eq!("regtest", regtest_keypair.coin.name);
+    assert!(regtest_keypair.uncompressed.wif.as_str().starts_with('9'));
+    assert!(regtest_keypair.compressed.wif.as_str().starts_with('c'));
+    let segwit = regtest_keypair.segwit_addresses();
+    assert_eq!(3, segwit.len());
+    assert!(segwit[0].1.as_str().starts_with("bcrt1q"));
+    assert!(segwit[1].1.as_str().starts_with('2'));
+    assert!(segwit[2].1.as_str().starts_with("bcrt1p"));
+
+    let dogecoin_keypair = WarpWallet::new(pass_phrase, salt).with_params(params).with_coin(CoinProfile::DOGECOIN).derive().unwrap();
+    assert_eq!(keypair.secret_exponent, dogecoin_keypair.secret_exponent);
+    assert!(dogecoin_keypair.compressed.address.as_str().starts_with('D'));
+    assert_eq!(None, dogecoin_keypair.p2wpkh);
+    assert!(dogecoin_keypair.segwit_addresses().is_empty());
+
+    let invalid = WarpParams { scrypt_iterations: 3_000, ..params };
+    assert!(warp_wallet(pass_phrase, salt, &invalid).is_err());
+}
+
+pub fn perform_warp_pbkdf2(pass_phrase: &str, salt: &str, params: &WarpParams) -> Result<Vec<u8>, WarpError> {
+    perform_pbkdf2(
+        add_byte_to_string(pass_phrase, params.pbkdf2_concat)?,
+        add_byte_to_string(salt, params.pbkdf2_concat)?,
+        params.pbkdf2_iterations,
+        WARP_KEY_LENGTH
+    )
+}
+
+pub fn perform_warp_scrypt(pass_phrase: &str, salt: &str, params: &WarpParams) -> Result<Vec<u8>, WarpError> {
     perform_scrypt(
-        add_byte_to_string(pass_phrase, WARP_SCRYPT_CONCAT),
-        add_byte_to_string(salt, WARP_SCRYPT_CONCAT),
-        WARP_SCRYPT_ITERATIONS,
-        WARP_SCRYPT_MEM_DIFF,
-        WARP_SCRYPT_PAR_DIFF,
+        add_byte_to_string(pass_phrase, params.scrypt_concat)?,
+        add_byte_to_string(salt, params.scrypt_concat)?,
+        params.scrypt_iterations,
+        params.scrypt_mem_diff,
+        params.scrypt_par_diff,
         WARP_KEY_LENGTH
     )
 }
 
-pub fn add_byte_to_string(input_string: &str, add_byte: u8) -> Vec<u8> {
+pub fn add_byte_to_string(input_string: &str, add_byte: u8) -> Result<Vec<u8>, WarpError> {
     let owned_string = input_string.to_owned();
     thread::spawn(move || {
         let mut buf = BytesMut::with_capacity(owned_string.len() + 1);
         buf.put(owned_string.as_bytes());
         buf.put_u8(add_byte);
         buf.take().to_vec()
-    }).join().unwrap()
+    }).join().map_err(WarpError::from)
 }
 
 #[test]
@@ -160,8 +454,8 @@ fn test_string_byte_concatenation() {
     ];
     assert_eq!(expected, input_string.as_bytes());
 
-    assert_eq!(expectation_with_concat_one, add_byte_to_string("The quick fox jumped over the lazy brown dog", 1u8));
-    assert_eq!(expectation_with_concat_two, add_byte_to_string("The quick fox jumped over the lazy brown dog", 2u8));
+    assert_eq!(expectation_with_concat_one, add_byte_to_string("The quick fox jumped over the lazy brown dog", 1u8).unwrap());
+    assert_eq!(expectation_with_concat_two, add_byte_to_string("The quick fox jumped over the lazy brown dog", 2u8).unwrap());
 
     let base_string = "The quick fox jumped over the lazy brown dog";
     let base_string_length = base_string.len();
@@ -194,7 +488,7 @@ fn test_warp_pbkdf2_to_hex_string() {
     ];
     for test_vector in test_data {
         println!("testing phrase: [{}] with salt: [{}]", test_vector.1, test_vector.2);
-        assert_eq!(test_vector.0, hex::encode(perform_warp_pbkdf2(test_vector.1, test_vector.2)));
+        assert_eq!(test_vector.0, hex::encode(perform_warp_pbkdf2(test_vector.1, test_vector.2, &WarpParams::keybase_v1()).unwrap()));
     }
 }
 
@@ -211,8 +505,8 @@ fn test_warp_wallet_to_private_key_wif() {
     ];
     for tuple in vectors {
         println!("Testing passphrase [{}] with salt [{}].", tuple.1, tuple.2);
-        let secret_exponent = warp_wallet(tuple.1, tuple.2);
-        let private_key_wif = secret_exponent_to_private_key(secret_exponent, false);
+        let secret_exponent = warp_wallet(tuple.1, tuple.2, &WarpParams::keybase_v1()).unwrap();
+        let private_key_wif = secret_exponent_to_private_key(secret_exponent, false, Network::Bitcoin).unwrap();
         assert_eq!(tuple.0, private_key_wif);
     }
 }
@@ -229,7 +523,7 @@ fn test_warp_wallet_to_secret_exponent() {
 
     for tuple in vectors {
         println!("Testing passphrase [{}] with salt [{}].", tuple.1, tuple.2);
-        assert_eq!(tuple.0, hex::encode(warp_wallet(tuple.1, tuple.2)));
+        assert_eq!(tuple.0, hex::encode(warp_wallet(tuple.1, tuple.2, &WarpParams::keybase_v1()).unwrap()));
     }
 }
 
@@ -252,31 +546,32 @@ fn test_warp_scrypt_hex_string() {
     ];
     for tuple in vectors {
         println!("testing phrase: [{}] with salt: [{}]", &tuple.1, &tuple.2);
-        assert_eq!(tuple.0, hex::encode(perform_warp_scrypt(tuple.1, tuple.2)));
+        assert_eq!(tuple.0, hex::encode(perform_warp_scrypt(tuple.1, tuple.2, &WarpParams::keybase_v1()).unwrap()));
     }
 }
 
 #[test]
 fn test_complete_warp_wallet() {
     let vectors = get_warp_wallet_vectors();
+    let params = WarpParams::keybase_v1();
     for vector in vectors.iter() {
-        let generated_s1 = perform_warp_scrypt(&vector.passphrase, &vector.salt);
+        let generated_s1 = perform_warp_scrypt(&vector.passphrase, &vector.salt, &params).unwrap();
         assert_eq!(vector.seeds[0], hex::encode(&generated_s1));
 
-        let generated_s2 = perform_warp_pbkdf2(&vector.passphrase, &vector.salt);
+        let generated_s2 = perform_warp_pbkdf2(&vector.passphrase, &vector.salt, &params).unwrap();
         assert_eq!(vector.seeds[1], hex::encode(&generated_s2));
 
         let generated_xor_secret_exponent = xor(&generated_s1, &generated_s2);
         assert_eq!(vector.seeds[2], hex::encode(&generated_xor_secret_exponent));
 
-        let generated_warp_secret_exponent = warp_wallet(&vector.passphrase, &vector.salt);
+        let generated_warp_secret_exponent = warp_wallet(&vector.passphrase, &vector.salt, &params).unwrap();
         assert_eq!(vector.seeds[2], hex::encode(&generated_warp_secret_exponent));
 
         assert_eq!(&generated_xor_secret_exponent, &generated_warp_secret_exponent);
-        let generated_private_key = secret_exponent_to_private_key(generated_warp_secret_exponent, false);
+        let generated_private_key = secret_exponent_to_private_key(generated_warp_secret_exponent, false, Network::Bitcoin).unwrap();
         assert_eq!(vector.keys[0], generated_private_key);
 
-        let generated_address = private_key_wif_to_public_address(&generated_private_key);
+        let generated_address = private_key_wif_to_public_address(&generated_private_key).unwrap();
         assert_eq!(vector.keys[1], generated_address);
         println!("Completed assertions of phrase [{}] and salt [{}] becoming", vector.passphrase, vector.salt);
         println!("address [{}] with privkey [{}].", vector.keys[1], vector.keys[0]);
@@ -0,0 +1,167 @@
+// Passphrase candidates streamed from wordlist files, one per line.
+// Lines are raw bytes, a wordlist does not have to be UTF-8. A trailing \n or \r\n is stripped and
+// empty lines are skipped. Gzip files are recognised by their magic bytes, not their name.
+
+extern crate flate2;
+
+use self::flate2::bufread::MultiGzDecoder;
+use error::WarpError;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::PathBuf;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The wordlist currently being read.
+struct Source {
+    name: String,
+    reader: Box<dyn BufRead + Send>,
+    line: u64
+}
+
+/// Streams the lines of any number of wordlists, in order, without loading them.
+pub struct Wordlist {
+    pending: VecDeque<PathBuf>,
+    current: Option<Source>,
+    failed: bool
+}
+
+impl Wordlist {
+    /**
+     * Files are opened one at a time when reading reaches them. "-" reads stdin.
+     */
+    pub fn open<I, P>(paths: I) -> Wordlist
+        where I: IntoIterator<Item = P>, P: Into<PathBuf> {
+        Wordlist { pending: paths.into_iter().map(Into::into).collect(), current: None, failed: false }
+    }
+
+    pub fn from_reader<R: BufRead + Send + 'static>(name: &str, reader: R) -> Wordlist {
+        let mut wordlist = Wordlist::open(Vec::<PathBuf>::new());
+        wordlist.current = Some(Source { name: name.to_owned(), reader: decompressed(Box::new(reader)), line: 0 });
+        wordlist
+    }
+
+    fn open_next(&mut self) -> Result<bool, WarpError> {
+        let path = match self.pending.pop_front() {
+            Some(path) => path,
+            None => return Ok(false)
+        };
+        let name = path.display().to_string();
+        let reader: Box<dyn BufRead + Send> = if name == "-" {
+            Box::new(BufReader::new(io::stdin()))
+        } else {
+            let file = File::open(&path).map_err(|error| WarpError::Io(format!("{}: {}", name, error)))?;
+            Box::new(BufReader::new(file))
+        };
+        self.current = Some(Source { name, reader: decompressed(reader), line: 0 });
+        Ok(true)
+    }
+
+    fn next_line(&mut self) -> Result<Option<Vec<u8>>, WarpError> {
+        loop {
+            if self.current.is_none() && !self.open_next()? {
+                return Ok(None);
+            }
+            let source = self.current.as_mut().expect("a wordlist was just opened");
+            let mut line = vec![];
+            let read = source.reader.read_until(b'\n', &mut line)
+                .map_err(|error| WarpError::Io(format!("{} line {}: {}", source.name, source.line + 1, error)))?;
+            if read == 0 {
+                self.current = None;
+                continue;
+            }
+            source.line += 1;
+            if line.last() == Some(&b'\n') {
+                line.pop();
+                if line.last() == Some(&b'\r') {
+                    line.pop();
+                }
+            }
+            if !line.is_empty() {
+                return Ok(Some(line));
+            }
+        }
+    }
+}
+
+/**
+ * Wraps the reader in a gzip decoder if the data starts with the gzip magic bytes.
+ */
+fn decompressed(mut reader: Box<dyn BufRead + Send>) -> Box<dyn BufRead + Send> {
+    let is_gzip = reader.fill_buf().map(|buffer| buffer.starts_with(&GZIP_MAGIC)).unwrap_or(false);
+    if is_gzip {
+        Box::new(BufReader::new(MultiGzDecoder::new(reader)))
+    } else {
+        reader
+    }
+}
+
+impl Iterator for Wordlist {
+    type Item = Result<Vec<u8>, WarpError>;
+
+    /**
+     * Stops after the first error, a wordlist that can not be read to the end is not skipped silently.
+     */
+    fn next(&mut self) -> Option<Result<Vec<u8>, WarpError>> {
+        if self.failed {
+            return None;
+        }
+        match self.next_line() {
+            Ok(line) => line.map(Ok),
+            Err(error) => {
+                self.failed = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_wordlist_lines() {
+    use std::io::Cursor;
+
+    let input = b"Dutch Power Cows\r\n\npassword\n\xff\xfe bytes\r\n\r\nno newline".to_vec();
+    let words: Vec<Vec<u8>> = Wordlist::from_reader("test", Cursor::new(input)).map(Result::unwrap).collect();
+    assert_eq!(vec![
+        b"Dutch Power Cows".to_vec(),
+        b"password".to_vec(),
+        b"\xff\xfe bytes".to_vec(),
+        b"no newline".to_vec()
+    ], words);
+    // Only one line ending is stripped, a lone \r inside or at the end of a line without \n stays.
+    let words: Vec<Vec<u8>> = Wordlist::from_reader("test", Cursor::new(b"a\rb\nc\r".to_vec())).map(Result::unwrap).collect();
+    assert_eq!(vec![b"a\rb".to_vec(), b"c\r".to_vec()], words);
+}
+
+#[test]
+fn test_wordlist_files() {
+    use self::flate2::write::GzEncoder;
+    use self::flate2::Compression;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    let plain = env::temp_dir().join("rust-warpwallet-test-wordlist.txt");
+    let gzipped = env::temp_dir().join("rust-warpwallet-test-wordlist.txt.gz");
+    fs::write(&plain, b"one\r\ntwo\r\n").unwrap();
+    let mut encoder = GzEncoder::new(File::create(&gzipped).unwrap(), Compression::default());
+    encoder.write_all(b"three\nfour\n").unwrap();
+    encoder.finish().unwrap();
+
+    let words: Vec<Vec<u8>> = Wordlist::open(vec![&plain, &gzipped]).map(Result::unwrap).collect();
+    assert_eq!(vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec(), b"four".to_vec()], words);
+
+    let missing = env::temp_dir().join("rust-warpwallet-test-missing-wordlist.txt");
+    let mut wordlist = Wordlist::open(vec![&plain, &missing, &gzipped]);
+    assert_eq!(Some(Ok(b"one".to_vec())), wordlist.next());
+    assert_eq!(Some(Ok(b"two".to_vec())), wordlist.next());
+    match wordlist.next() {
+        Some(Err(WarpError::Io(_))) => (),
+        other => panic!("expected an i/o error, got {:?}", other)
+    }
+    assert_eq!(None, wordlist.next());
+
+    fs::remove_file(&plain).unwrap();
+    fs::remove_file(&gzipped).unwrap();
+}